@@ -0,0 +1,125 @@
+use crate::{TestTempFile, TestTempFileBuilder};
+use std::io::SeekFrom;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite, ReadBuf};
+
+/// Async counterpart of [`TestTempFile`] wrapping a [`tokio::fs::File`], available with
+/// the `tokio` feature.
+///
+/// Naming and deletion on drop work exactly like [`TestTempFile`], the file is removed
+/// synchronously in `Drop` since there is no async drop.
+///
+/// # Examples
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// use tokio::io::AsyncWriteExt;
+/// use test_temp_file::AsyncTestTempFile;
+/// let mut t = AsyncTestTempFile::new(String::from("file_name.txt")).await.unwrap();
+/// t.write_all(b"some bytes").await.unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AsyncTestTempFile {
+    file: tokio::fs::File,
+    temp: TestTempFile
+}
+
+impl AsyncTestTempFile {
+    /// Creates the file in the current working directory, see [`TestTempFile::new`]
+    /// for how it's named. The file is created on a blocking thread, but a directory set
+    /// with [`TestTempFile::set_thread_default_dir`] on the calling thread still applies.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    pub async fn new(filename: String) -> crate::Result<AsyncTestTempFile> {
+        // the builder picks up the calling thread's default directory before moving over
+        let builder = TestTempFileBuilder::new().name(filename);
+        let temp = tokio::task::spawn_blocking(move || builder.build())
+            .await
+            .map_err(std::io::Error::other)??;
+        let file = tokio::fs::File::from_std(temp.try_clone_handle()?);
+        Ok(AsyncTestTempFile { file, temp })
+    }
+
+    /// Path of the generated file, exactly as it was used to open it
+    pub fn path(&self) -> &Path {
+        self.temp.path()
+    }
+}
+
+impl AsRef<Path> for AsyncTestTempFile {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl AsyncRead for AsyncTestTempFile {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.file).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for AsyncTestTempFile {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.file).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.file).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for AsyncTestTempFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        Pin::new(&mut self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        Pin::new(&mut self.file).poll_complete(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+    static FILE_NAME: &str = "async_test_file.txt";
+
+    #[tokio::test]
+    async fn test_write_and_read() {
+        let mut t = AsyncTestTempFile::new(String::from(FILE_NAME)).await.unwrap();
+        t.write_all(b"some bytes").await.unwrap();
+        t.flush().await.unwrap();
+        t.seek(SeekFrom::Start(0)).await.unwrap();
+        let mut content = String::new();
+        t.read_to_string(&mut content).await.unwrap();
+        assert_eq!(content, "some bytes");
+    }
+
+    #[tokio::test]
+    async fn test_thread_default_dir() {
+        let dir = crate::TestTempDir::new(String::from("async_default_dir"));
+        TestTempFile::set_thread_default_dir(Some(dir.path().to_path_buf()));
+        let t = AsyncTestTempFile::new(String::from(FILE_NAME)).await;
+        TestTempFile::set_thread_default_dir(None);
+        assert_eq!(t.unwrap().path().parent(), Some(dir.path()));
+    }
+
+    #[tokio::test]
+    async fn test_drop_deletes() {
+        let t = AsyncTestTempFile::new(String::from(FILE_NAME)).await.unwrap();
+        let path = t.path().to_path_buf();
+        assert!(path.exists());
+        drop(t);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,1213 @@
+use crate::name;
+use crate::TestTempFile;
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How [`TestTempFile::write_str`] and [`TestTempFile::append_line`] write newlines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NewlineMode {
+    /// Writes exactly the given bytes on every platform
+    #[default]
+    Raw,
+    /// Writes `\n` as `\r\n` on Windows and exactly the given bytes everywhere else
+    CrlfOnWindows
+}
+
+impl NewlineMode {
+    /// `s` with newlines converted for the current platform, `\r\n` is left alone
+    pub(crate) fn translate<'a>(self, s: &'a str) -> Cow<'a, str> {
+        if self == NewlineMode::Raw || !cfg!(windows) || !s.contains('\n') {
+            return Cow::Borrowed(s);
+        }
+        let mut translated = String::with_capacity(s.len() + s.len() / 8);
+        let mut previous = None;
+        for c in s.chars() {
+            if c == '\n' && previous != Some('\r') {
+                translated.push('\r');
+            }
+            translated.push(c);
+            previous = Some(c);
+        }
+        Cow::Owned(translated)
+    }
+}
+
+/// Characters the random component of generated names is made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RandomAlphabet {
+    /// Decimal digits, the default
+    #[default]
+    Numeric,
+    /// `0-9`, `A-Z` and `a-z`
+    AlphaNumeric,
+    /// Lowercase hexadecimal digits
+    Hex
+}
+
+impl RandomAlphabet {
+    pub(crate) fn chars(self) -> &'static [u8] {
+        match self {
+            RandomAlphabet::Numeric => b"0123456789",
+            RandomAlphabet::AlphaNumeric => b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
+            RandomAlphabet::Hex => b"0123456789abcdef"
+        }
+    }
+}
+
+/// Callback registered with [`TestTempFileBuilder::on_drop`], the mutex only makes it `Sync`.
+/// Clones start without one since it can only run once.
+#[derive(Default)]
+struct DropHook(Option<std::sync::Mutex<DropFn>>);
+
+type DropFn = Box<dyn FnOnce(&Path) + Send>;
+
+impl Clone for DropHook {
+    fn clone(&self) -> Self {
+        DropHook(None)
+    }
+}
+
+impl std::fmt::Debug for DropHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.is_some() { "Some(..)" } else { "None" })
+    }
+}
+
+/// Configures how a [`TestTempFile`] is named, where it's created and whether it's
+/// deleted on drop.
+///
+/// The final file name is `{prefix}{random}{suffix}` inside `dir`.
+/// When nothing is set the file is created in the current working directory as `_{random}`.
+/// By default `{random}` is `ttf` followed by 20 decimal digits, the marker
+/// [`cleanup_orphans`](crate::cleanup_orphans) looks for.
+///
+/// # Examples
+/// ```
+/// use test_temp_file::TestTempFileBuilder;
+/// let t = TestTempFileBuilder::new()
+///     .dir(std::env::temp_dir())
+///     .prefix(String::from("report_"))
+///     .suffix(String::from(".csv"))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestTempFileBuilder {
+    dir: PathBuf,
+    subdir: PathBuf,
+    prefix: String,
+    suffix: String,
+    template: Option<String>,
+    filename: Option<String>,
+    os_name: Option<(OsString, OsString)>,
+    keep_on_drop: bool,
+    keep_on_panic: bool,
+    append: bool,
+    readable: bool,
+    writable: bool,
+    seed: Option<u64>,
+    exclusive: bool,
+    lock_names: bool,
+    pid_and_time: bool,
+    randomize: bool,
+    dual_cursor: bool,
+    tmp_suffix: bool,
+    buffer_capacity: usize,
+    flush_before_read: bool,
+    size_limit: Option<u64>,
+    options: Option<OpenOptions>,
+    random_len: Option<usize>,
+    random_alphabet: RandomAlphabet,
+    newline_mode: NewlineMode,
+    on_drop: DropHook,
+    #[cfg(unix)]
+    mode: Option<u32>
+}
+
+/// Where [`TestTempFileBuilder::template`] puts the random component
+const RAND_PLACEHOLDER: &str = "{rand}";
+
+/// Stem used in place of an empty file name, so `""` becomes `tmp_{random}`
+const EMPTY_STEM: &str = "tmp";
+
+thread_local! {
+    /// Directory files are created in when none is given, see [`TestTempFile::set_thread_default_dir`]
+    static DEFAULT_DIR: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+pub(crate) fn set_default_dir(dir: Option<PathBuf>) {
+    DEFAULT_DIR.with(|default| *default.borrow_mut() = dir);
+}
+
+fn default_dir() -> PathBuf {
+    DEFAULT_DIR.with(|default| default.borrow().clone()).unwrap_or_default()
+}
+
+impl Default for TestTempFileBuilder {
+    fn default() -> Self {
+        TestTempFileBuilder {
+            dir: default_dir(),
+            subdir: PathBuf::new(),
+            prefix: String::from("_"),
+            suffix: String::new(),
+            template: None,
+            filename: None,
+            os_name: None,
+            keep_on_drop: false,
+            keep_on_panic: false,
+            append: false,
+            readable: true,
+            writable: true,
+            seed: None,
+            exclusive: false,
+            lock_names: false,
+            pid_and_time: false,
+            randomize: true,
+            dual_cursor: false,
+            tmp_suffix: false,
+            buffer_capacity: 0,
+            flush_before_read: true,
+            size_limit: None,
+            options: None,
+            random_len: None,
+            random_alphabet: RandomAlphabet::default(),
+            newline_mode: NewlineMode::default(),
+            on_drop: DropHook::default(),
+            #[cfg(unix)]
+            mode: None
+        }
+    }
+}
+
+impl TestTempFileBuilder {
+    pub fn new() -> TestTempFileBuilder {
+        TestTempFileBuilder::default()
+    }
+
+    /// Directory the file is created in, defaults to the current working directory.
+    /// An absolute path is used as is, which places the file on whatever filesystem is
+    /// mounted there.
+    pub fn dir(mut self, dir: PathBuf) -> Self {
+        self.dir = dir;
+        self
+    }
+
+    /// Text placed before the random component, defaults to `_`
+    pub fn prefix(mut self, prefix: String) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Text placed after the random component, defaults to nothing
+    pub fn suffix(mut self, suffix: String) -> Self {
+        self.suffix = suffix;
+        self
+    }
+
+    /// Spells out the whole file name with `{rand}` standing for the random component, for
+    /// example `fixture-{rand}-v2.json`, instead of `{prefix}{random}{suffix}`. The prefix and
+    /// suffix are ignored then. Building fails with [`ErrorKind::InvalidInput`] if `template`
+    /// has no `{rand}`, only the first one is replaced.
+    pub fn template(mut self, template: String) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// When `true` the file is left on disk when the [`TestTempFile`] is dropped
+    pub fn keep_on_drop(mut self, keep_on_drop: bool) -> Self {
+        self.keep_on_drop = keep_on_drop;
+        self
+    }
+
+    /// When `true` the file is left on disk if it's dropped while the thread is panicking,
+    /// so a failing test keeps its files for inspection and a passing one cleans up.
+    /// The kept path is printed to stderr.
+    pub fn keep_on_panic(mut self, keep_on_panic: bool) -> Self {
+        self.keep_on_panic = keep_on_panic;
+        self
+    }
+
+    pub(crate) fn get_keep_on_panic(&self) -> bool {
+        self.keep_on_panic
+    }
+
+    /// When `true` the file is opened in append mode, every write goes to the end of
+    /// the file regardless of seeks
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// When `false` the file is opened without read access, so reading it fails with the
+    /// error the OS reports, for testing code that has to cope with that. Defaults to
+    /// `true`, ignored when [`options`](TestTempFileBuilder::options) are given.
+    pub fn readable(mut self, readable: bool) -> Self {
+        self.readable = readable;
+        self
+    }
+
+    /// When `false` the file is created and then reopened without write access, so writes
+    /// fail with the error the OS reports. Defaults to `true`. The file can't be both
+    /// unreadable and unwritable, building fails with [`ErrorKind::InvalidInput`] then.
+    pub fn writable(mut self, writable: bool) -> Self {
+        self.writable = writable;
+        self
+    }
+
+    /// Draws the random component from a generator seeded with `seed`, `StdRng` with the
+    /// `rand` feature and SplitMix64 without it, so the same seed always generates the same
+    /// name. Instead of trying another name, building fails with [`ErrorKind::AlreadyExists`]
+    /// when that name is taken.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// When `true` building fails with [`ErrorKind::AlreadyExists`] as soon as the
+    /// generated path exists, instead of trying another random name
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// When `true` a `{name}.lock` file is created next to every candidate name before the
+    /// file itself is opened and removed right after, so processes sharing a directory never
+    /// pick the same name at the same time even when they draw the same number. A candidate
+    /// whose lock is held by someone else counts as taken and the next number is tried.
+    pub fn lock_names(mut self, lock_names: bool) -> Self {
+        self.lock_names = lock_names;
+        self
+    }
+
+    /// When `true` the process id and the creation time in milliseconds since the epoch
+    /// are added in front of the random component, `{prefix}{pid}_{millis}_{random}{suffix}`,
+    /// so leftover files can be traced back to the run that created them
+    pub fn pid_and_time(mut self, pid_and_time: bool) -> Self {
+        self.pid_and_time = pid_and_time;
+        self
+    }
+
+    /// When `false` no random component is added and the file is created under exactly
+    /// the given name, `data.json` stays `data.json` and a bare builder uses `{prefix}{suffix}`.
+    /// An existing file with that name is truncated and reused, unless
+    /// [`exclusive`](TestTempFileBuilder::exclusive) is set, in which case building fails
+    /// with [`ErrorKind::AlreadyExists`]. The file is still deleted on drop. Defaults to `true`.
+    pub fn randomize(mut self, randomize: bool) -> Self {
+        self.randomize = randomize;
+        self
+    }
+
+    /// When `true` a second, read-only handle is opened so reads have their own cursor.
+    /// [`Read`](std::io::Read) and the `read_to_*` helpers then go through that handle
+    /// while writes and seeks keep using the original one, so reading never moves where
+    /// the next write goes.
+    pub fn dual_cursor(mut self, dual_cursor: bool) -> Self {
+        self.dual_cursor = dual_cursor;
+        self
+    }
+
+    /// When `true` `.tmp` is added after the suffix, `data.json` becomes `data_{random}.json.tmp`,
+    /// for the write-then-rename pattern finished by [`TestTempFile::commit`]
+    pub fn tmp_suffix(mut self, tmp_suffix: bool) -> Self {
+        self.tmp_suffix = tmp_suffix;
+        self
+    }
+
+    /// Collects writes in a buffer of `capacity` bytes instead of passing each one to the OS,
+    /// for tests making many small writes. The buffer is flushed by `flush`, `sync`, reads,
+    /// seeks and on drop, reads always see everything written before. Defaults to 0, unbuffered.
+    pub fn buffered(mut self, capacity: usize) -> Self {
+        self.buffer_capacity = capacity;
+        self
+    }
+
+    /// When `true`, the default, [`Read`](std::io::Read) flushes pending buffered writes before
+    /// reading so a read never misses what was just written. Only matters together with
+    /// [`buffered`](TestTempFileBuilder::buffered), turning it off lets a test observe what
+    /// actually reached the file. The `read_to_*` helpers always flush.
+    pub fn flush_before_read(mut self, flush_before_read: bool) -> Self {
+        self.flush_before_read = flush_before_read;
+        self
+    }
+
+    pub(crate) fn get_flush_before_read(&self) -> bool {
+        self.flush_before_read
+    }
+
+    /// Sets a size the file isn't expected to grow past, to catch runaway output. A file that
+    /// is larger when dropped gets a warning on stderr, [`TestTempFile::check_size_limit`]
+    /// turns it into an error for explicit checks.
+    pub fn warn_if_larger_than(mut self, bytes: u64) -> Self {
+        self.size_limit = Some(bytes);
+        self
+    }
+
+    pub(crate) fn get_size_limit(&self) -> Option<u64> {
+        self.size_limit
+    }
+
+    /// Opens the file with `options` instead of the defaults, for flags the builder doesn't
+    /// cover such as `custom_flags` on unix or `share_mode` on Windows. `write` and
+    /// `create_new` are always turned on so that a new file is created under the generated
+    /// name, [`append`](TestTempFileBuilder::append) is ignored.
+    pub fn options(mut self, options: OpenOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    /// Number of characters in the random component. By default the numeric component is
+    /// tagged and 20 digits long, with another [`random_alphabet`](TestTempFileBuilder::random_alphabet)
+    /// it's as long as needed to write out the random number.
+    /// Shorter lengths keep the last characters, longer ones are padded with characters derived
+    /// from the same number, so there's never more than 64 bits of randomness.
+    /// Building fails with [`ErrorKind::InvalidInput`] for a length of 0.
+    pub fn random_len(mut self, random_len: usize) -> Self {
+        self.random_len = Some(random_len);
+        self
+    }
+
+    /// Characters the random component is made of, defaults to [`RandomAlphabet::Numeric`].
+    /// [`cleanup_orphans`](crate::cleanup_orphans) only recognizes the default numeric names.
+    pub fn random_alphabet(mut self, random_alphabet: RandomAlphabet) -> Self {
+        self.random_alphabet = random_alphabet;
+        self
+    }
+
+    /// How the text helpers write newlines, defaults to [`NewlineMode::Raw`]
+    pub fn newline_mode(mut self, newline_mode: NewlineMode) -> Self {
+        self.newline_mode = newline_mode;
+        self
+    }
+
+    pub(crate) fn get_newline_mode(&self) -> NewlineMode {
+        self.newline_mode
+    }
+
+    /// Calls `f` with the path of the file when it's dropped, right before it's deleted.
+    /// A panic in `f` is caught and reported on stderr instead of unwinding out of drop.
+    /// Files created by [`TestTempFile::try_clone`] don't inherit the callback.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFileBuilder;
+    /// let t = TestTempFileBuilder::new()
+    ///     .on_drop(|path| println!("removing {}", path.display()))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn on_drop<F: FnOnce(&Path) + Send + 'static>(mut self, f: F) -> Self {
+        self.on_drop = DropHook(Some(std::sync::Mutex::new(Box::new(f))));
+        self
+    }
+
+    /// Runs the [`on_drop`](TestTempFileBuilder::on_drop) callback, at most once
+    pub(crate) fn run_on_drop(&mut self, path: &Path) {
+        let hook = match self.on_drop.0.take() {
+            Some(hook) => hook.into_inner().unwrap_or_else(|e| e.into_inner()),
+            None => return
+        };
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| hook(path)));
+        if result.is_err() {
+            eprintln!("test-temp-file: on_drop callback panicked for {}", path.display());
+        }
+    }
+
+    /// Permission bits the file is created with, see
+    /// [`OpenOptionsExt::mode`](std::os::unix::fs::OpenOptionsExt::mode). The process umask
+    /// still applies, a mode of `0o666` under the usual umask of `0o022` ends up as `0o644`.
+    /// Use [`TestTempFile::mode_bits`] to check what the file really got.
+    #[cfg(unix)]
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Same configuration with a fresh random name, used for files derived from another one
+    /// so they never end up at its path
+    pub(crate) fn without_seed(mut self) -> Self {
+        self.seed = None;
+        self.randomize = true;
+        self
+    }
+
+    /// Uses the `{stem}_{random}.{ext}` scheme of [`TestTempFile::new`].
+    /// Any directories in `filename` are kept as they are and created if missing.
+    pub(crate) fn name(self, filename: String) -> Self {
+        let (_, base) = split_subdir(&filename);
+        let (stem, ext) = match Path::new(base).extension() {
+            Some(ext) => {
+                let stem_len = base.len() - ext.len() - 1;
+                (String::from(&base[..stem_len]), String::from(&base[stem_len + 1..]))
+            },
+            None => (String::from(base), String::new())
+        };
+        self.stem_and_extension(&filename, &stem, &ext)
+    }
+
+    /// Uses `{stem}_{random}.{ext}` with `stem` and `ext` taken as they are,
+    /// directories in `stem` are handled like in [`name`](TestTempFileBuilder::name)
+    pub(crate) fn name_with_extension(self, stem: &str, ext: &str) -> Self {
+        let filename = if ext.is_empty() { String::from(stem) } else { format!("{}.{}", stem, ext) };
+        self.stem_and_extension(&filename, split_subdir(stem).1, ext)
+    }
+
+    /// Same scheme as [`name`](TestTempFileBuilder::name) for names that may not be UTF-8.
+    /// The prefix and suffix are kept as `OsString`s so the created path has exactly the
+    /// bytes of `filename`, only the name reported by `Debug` is converted lossily.
+    pub(crate) fn name_os(mut self, filename: &OsStr) -> Self {
+        let path = Path::new(filename);
+        let mut prefix = path.file_stem().map(OsString::from).unwrap_or_else(|| OsString::from(EMPTY_STEM));
+        prefix.push("_");
+        let mut suffix = OsString::new();
+        if let Some(ext) = path.extension() {
+            suffix.push(".");
+            suffix.push(ext);
+        }
+        self.subdir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        self.filename = Some(filename.to_string_lossy().into_owned());
+        self.os_name = Some((prefix, suffix));
+        self
+    }
+
+    fn stem_and_extension(self, filename: &str, stem: &str, ext: &str) -> Self {
+        let stem = if stem.is_empty() { EMPTY_STEM } else { stem };
+        let suffix = if ext.is_empty() { String::new() } else { format!(".{}", ext) };
+        let mut builder = self.prefix(format!("{}_", stem)).suffix(suffix);
+        builder.subdir = PathBuf::from(split_subdir(filename).0);
+        builder.filename = Some(String::from(filename));
+        builder
+    }
+
+    /// Creates the file, drawing a new random component whenever the generated name
+    /// already exists. Fails with [`ErrorKind::AlreadyExists`] if no free name was found,
+    /// and with [`ErrorKind::InvalidInput`] if the prefix, suffix or name contain characters
+    /// not allowed in file names. Any other error from the OS is returned unchanged.
+    ///
+    /// Long prefixes are shortened so generated names stay within 200 bytes, the random
+    /// component and the suffix are always kept whole.
+    pub fn build(self) -> crate::Result<TestTempFile> {
+        if !self.randomize {
+            return self.build_from(1, || 0);
+        }
+        match self.seed {
+            Some(seed) => self.build_from(1, name::seeded(seed)),
+            None if self.exclusive => self.build_from(1, name::process_unique),
+            None => self.build_from(name::MAX_ATTEMPTS, name::process_unique)
+        }
+    }
+
+    /// Creates `count` files that differ only by their index, named `{prefix}{i}_{random}{suffix}`
+    /// for `i` in `0..count`. They are returned in order and deleted when dropped like any
+    /// other file. An [`on_drop`](TestTempFileBuilder::on_drop) callback is only attached to
+    /// the last one.
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] when [`randomize`](TestTempFileBuilder::randomize)
+    /// is off, and with the first error [`build`](TestTempFileBuilder::build) returns, the
+    /// files created up to then are deleted again.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFileBuilder;
+    /// let shards = TestTempFileBuilder::new().prefix(String::from("shard_")).build_many(3).unwrap();
+    /// assert_eq!(shards.len(), 3);
+    /// ```
+    pub fn build_many(self, count: usize) -> crate::Result<Vec<TestTempFile>> {
+        if !self.randomize {
+            return Err(std::io::Error::new(ErrorKind::InvalidInput, "build_many needs randomized names").into());
+        }
+        let mut files = Vec::with_capacity(count);
+        if count == 0 {
+            return Ok(files);
+        }
+        // The last file is built first so it owns any directories created for the batch,
+        // the Vec drops it after all the others. Clones don't carry the on_drop callback,
+        // so it's built from the original builder.
+        let rest = self.clone();
+        files.push(self.indexed(count - 1).build()?);
+        for index in (0..count - 1).rev() {
+            files.push(rest.clone().indexed(index).build()?);
+        }
+        files.reverse();
+        Ok(files)
+    }
+
+    fn indexed(mut self, index: usize) -> Self {
+        self.prefix = format!("{}{}_", self.prefix, index);
+        self
+    }
+
+    /// Like [`build`](TestTempFileBuilder::build) with the random numbers drawn from `draw`
+    #[cfg(feature = "rand")]
+    pub(crate) fn build_with<D: FnMut() -> u64>(self, draw: D) -> crate::Result<TestTempFile> {
+        self.build_from(name::MAX_ATTEMPTS, draw)
+    }
+
+    /// Picks a free name the way [`build`](TestTempFileBuilder::build) does without creating
+    /// the file, only the missing directories are. Returns the path and those directories.
+    pub(crate) fn reserve(self) -> std::io::Result<(PathBuf, Vec<PathBuf>)> {
+        self.validate_names()?;
+        let created_dirs = create_missing_dirs(&self.dir, &self.subdir)?;
+        let dir = self.dir.join(&self.subdir);
+        let attempts = if self.randomize { name::MAX_ATTEMPTS } else { 1 };
+        let result = name::create_unique(attempts, name::process_unique, |random_number| {
+            let path = dir.join(self.file_name(random_number)?);
+            match std::fs::symlink_metadata(&path) {
+                Ok(_) => Err(std::io::Error::new(ErrorKind::AlreadyExists, format!("{} already exists", path.display()))),
+                Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(path),
+                Err(e) => Err(e)
+            }
+        });
+        match result {
+            Ok((_, path)) => Ok((path, created_dirs)),
+            Err(e) => {
+                remove_dirs(&created_dirs);
+                Err(e)
+            }
+        }
+    }
+
+    /// Fails with [`ErrorKind::InvalidInput`] when the parts of the name can't make up a
+    /// valid file name
+    fn validate_names(&self) -> std::io::Result<()> {
+        if self.random_len == Some(0) {
+            return Err(std::io::Error::new(ErrorKind::InvalidInput, "random_len must be at least 1"));
+        }
+        name::validate(&self.prefix)?;
+        name::validate(&self.suffix)?;
+        if let Some(ref template) = self.template {
+            name::validate(template)?;
+            if !template.contains(RAND_PLACEHOLDER) {
+                return Err(name::invalid_name(template, "a template needs a {rand} placeholder"));
+            }
+        }
+        // The subdir may legitimately hold a drive prefix on Windows, so only null bytes are rejected there
+        if self.subdir.to_string_lossy().contains('\0') {
+            name::validate(&self.subdir.to_string_lossy())?;
+        }
+        Ok(())
+    }
+
+    fn build_from<D: FnMut() -> u64>(self, attempts: usize, draw: D) -> crate::Result<TestTempFile> {
+        if !self.readable && !self.writable {
+            return Err(std::io::Error::new(ErrorKind::InvalidInput, "a file has to be readable or writable").into());
+        }
+        self.validate_names()?;
+        let created_dirs = create_missing_dirs(&self.dir, &self.subdir)?;
+        let dir = self.dir.join(&self.subdir);
+        let result = name::create_unique(attempts, draw, |random_number| {
+            let final_filename = dir.join(self.file_name(random_number)?);
+            let _lock = if self.lock_names { Some(name::NameLock::acquire(&final_filename)?) } else { None };
+            self.open_options().
+                open(&final_filename).
+                map(|file| (final_filename, file))
+        });
+        match result {
+            Ok((random_number, (final_filename, file))) => {
+                // creating always needs write access, it's dropped by opening the file again
+                let file = if self.writable { file } else { OpenOptions::new().read(true).open(&final_filename)? };
+                let dual_cursor = self.dual_cursor;
+                let mut t = self.finish(random_number, final_filename, file, created_dirs);
+                if dual_cursor {
+                    t.reader = Some(t.reopen_readonly()?);
+                }
+                Ok(t)
+            },
+            Err(e) => {
+                remove_dirs(&created_dirs);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Opens the existing file at `path` for reading and writing and manages it like one this
+    /// builder created, the configuration is used for files derived from it
+    pub(crate) fn adopt(self, path: PathBuf) -> std::io::Result<TestTempFile> {
+        let file = OpenOptions::new().read(true).write(true).open(&path)?;
+        Ok(self.finish(0, path, file, Vec::new()))
+    }
+
+    /// Creates a new file at exactly `path`, failing with [`ErrorKind::AlreadyExists`] if
+    /// there is one, and manages it with `random_number` as its random component
+    pub(crate) fn create_at(self, path: PathBuf, random_number: u64) -> std::io::Result<TestTempFile> {
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        Ok(self.finish(random_number, path, file, Vec::new()))
+    }
+
+    /// The generated file name, with the prefix shortened so the whole name fits in
+    /// [`name::MAX_NAME_LEN`] bytes. Verbatim names are used as they are.
+    fn file_name(&self, random_number: u64) -> std::io::Result<OsString> {
+        let random = match (self.random_alphabet, self.random_len) {
+            (RandomAlphabet::Numeric, None) => name::tagged(random_number),
+            (alphabet, len) => name::encode(random_number, alphabet.chars(), len)
+        };
+        let tmp = if self.tmp_suffix { ".tmp" } else { "" };
+        if let (true, Some((prefix, suffix))) = (self.randomize, &self.os_name) {
+            let mut name = prefix.clone();
+            name.push(random);
+            name.push(suffix);
+            name.push(tmp);
+            return Ok(name);
+        }
+        let random = if !self.randomize {
+            let base = self.filename.as_ref().and_then(|f| Path::new(f).file_name());
+            return Ok(match base {
+                Some(base) => format!("{}{}", base.to_string_lossy(), tmp),
+                None => format!("{}{}{}", self.prefix, self.suffix, tmp)
+            }.into());
+        } else if self.pid_and_time {
+            let millis = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis())
+                .unwrap_or(0);
+            let pid = std::process::id();
+            format!("{}_{}_{}", pid, millis, random)
+        } else {
+            random
+        };
+        if let Some(ref template) = self.template {
+            return Ok(format!("{}{}", template.replacen(RAND_PLACEHOLDER, &random, 1), tmp).into());
+        }
+        let prefix = name::shorten_prefix(&self.prefix, random.len() + self.suffix.len() + tmp.len())?;
+        Ok(format!("{}{}{}{}", prefix, random, self.suffix, tmp).into())
+    }
+
+    fn open_options(&self) -> OpenOptions {
+        let mut options = match self.options {
+            Some(ref options) => options.clone(),
+            None => {
+                let mut options = OpenOptions::new();
+                options.
+                    append(self.append).
+                    read(self.readable);
+                options
+            }
+        };
+        if self.randomize || self.exclusive {
+            options.create_new(true);
+        } else {
+            options.create(true).truncate(true);
+        }
+        options.write(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            if let Some(mode) = self.mode {
+                options.mode(mode);
+            }
+        }
+        options
+    }
+
+    fn finish(
+        self,
+        random_number: u64,
+        final_filename: PathBuf,
+        file: std::fs::File,
+        created_dirs: Vec<PathBuf>
+    ) -> TestTempFile {
+        let filename = match self.filename {
+            Some(ref filename) => filename.clone(),
+            None => format!("{}{}", self.prefix, self.suffix)
+        };
+        TestTempFile {
+            filename,
+            random_number,
+            final_filename,
+            file: Some(std::io::BufWriter::with_capacity(self.buffer_capacity, file)),
+            reader: None,
+            created_dirs,
+            symlinks: std::sync::Mutex::new(Vec::new()),
+            delete_on_drop: !self.keep_on_drop,
+            commit_target: None,
+            cached_contents: None,
+            builder: self,
+            owning_dir: None
+        }
+    }
+}
+
+/// Splits `filename` at its last separator into the directories and the file name
+fn split_subdir(filename: &str) -> (&str, &str) {
+    match filename.rfind(std::path::is_separator) {
+        Some(index) => (&filename[..index], &filename[index + 1..]),
+        None => ("", filename)
+    }
+}
+
+/// Creates every component of `subdir` missing under `dir`, returning the ones created
+fn create_missing_dirs(dir: &Path, subdir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut created = Vec::new();
+    let mut current = dir.to_path_buf();
+    for component in subdir.components() {
+        current.push(component);
+        if current.is_dir() {
+            continue;
+        }
+        match std::fs::create_dir(&current) {
+            Ok(()) => created.push(current.clone()),
+            Err(ref e) if e.kind() == ErrorKind::AlreadyExists => (),
+            Err(e) => {
+                remove_dirs(&created);
+                return Err(e);
+            }
+        }
+    }
+    Ok(created)
+}
+
+/// Removes directories returned by [`create_missing_dirs`], innermost first,
+/// leaving any that aren't empty
+pub(crate) fn remove_dirs(dirs: &[PathBuf]) {
+    for dir in dirs.iter().rev() {
+        let _ = std::fs::remove_dir(dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    fn file_name(t: &TestTempFile) -> String {
+        t.final_filename.file_name().unwrap().to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_default() {
+        let t = TestTempFileBuilder::new().build().unwrap();
+        let name = file_name(&t);
+        assert!(name.starts_with("_ttf"));
+        assert!(name["_ttf".len()..].parse::<u64>().is_ok(), "{}", name);
+        assert_eq!(name, format!("_{}", name::tagged(t.random_number)));
+        assert_eq!(t.final_filename.parent(), Some(PathBuf::new().as_path()));
+    }
+
+    #[test]
+    fn test_dir() {
+        let t = TestTempFileBuilder::new().dir(std::env::temp_dir()).build().unwrap();
+        assert_eq!(t.final_filename.parent(), Some(std::env::temp_dir().as_path()));
+        assert!(t.final_filename.exists());
+    }
+
+    #[test]
+    fn test_prefix() {
+        let t = TestTempFileBuilder::new().prefix(String::from("pre_")).build().unwrap();
+        let name = file_name(&t);
+        assert!(name.starts_with("pre_"));
+        assert_eq!(name, format!("pre_{}", name::tagged(t.random_number)));
+    }
+
+    #[test]
+    fn test_suffix() {
+        let t = TestTempFileBuilder::new().suffix(String::from(".json")).build().unwrap();
+        assert_eq!(t.final_filename.extension().unwrap(), "json");
+    }
+
+    #[test]
+    fn test_keep_on_drop() {
+        let t = TestTempFileBuilder::new().keep_on_drop(true).build().unwrap();
+        let path = t.final_filename.clone();
+        drop(t);
+        assert!(path.exists());
+        std::fs::remove_file(path).unwrap();
+
+        let t = TestTempFileBuilder::new().keep_on_drop(false).build().unwrap();
+        let path = t.final_filename.clone();
+        drop(t);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_existing_name_is_skipped() {
+        let dir = std::env::temp_dir();
+        let taken = name::seeded(7)();
+        let taken_path = dir.join(format!("collide_{}.txt", name::tagged(taken)));
+        std::fs::write(&taken_path, b"not ours").unwrap();
+
+        let t = TestTempFileBuilder::new()
+            .dir(dir)
+            .name(String::from("collide.txt"))
+            .build_from(name::MAX_ATTEMPTS, name::seeded(7))
+            .unwrap();
+        assert_ne!(t.final_filename, taken_path);
+        assert_ne!(t.random_number, taken);
+        assert_eq!(file_name(&t), format!("collide_{}.txt", name::tagged(t.random_number)));
+        assert_eq!(std::fs::read(&taken_path).unwrap(), b"not ours");
+        std::fs::remove_file(taken_path).unwrap();
+    }
+
+    #[test]
+    fn test_append() {
+        let mut t = TestTempFileBuilder::new().append(true).build().unwrap();
+        t.write_all(b"a").unwrap();
+        t.seek(SeekFrom::Start(0)).unwrap();
+        t.write_all(b"b").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "ab");
+    }
+
+    #[test]
+    fn test_nested_name() {
+        let root = std::env::temp_dir().join(format!("nested_{}", name::process_unique()));
+        let t = TestTempFileBuilder::new()
+            .dir(std::env::temp_dir())
+            .name(format!("{}/inner/fixture.txt", root.file_name().unwrap().to_str().unwrap()))
+            .build()
+            .unwrap();
+        let path = t.final_filename.clone();
+        assert_eq!(path.parent(), Some(root.join("inner").as_path()));
+        assert_eq!(file_name(&t), format!("fixture_{}.txt", name::tagged(t.random_number)));
+        assert!(path.exists());
+        drop(t);
+        assert!(!path.exists());
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn test_seed() {
+        let t = TestTempFileBuilder::new().seed(42).build().unwrap();
+        let path = t.final_filename.clone();
+        drop(t);
+        let t = TestTempFileBuilder::new().seed(42).build().unwrap();
+        assert_eq!(t.final_filename, path);
+        let e = TestTempFileBuilder::new().seed(42).build().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::AlreadyExists);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mode() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let t = TestTempFileBuilder::new().mode(0o400).build().unwrap();
+        let path = t.final_filename.clone();
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o400);
+        // root ignores permission bits
+        if metadata.uid() != 0 {
+            assert!(OpenOptions::new().write(true).open(&path).is_err());
+        }
+        drop(t);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_missing_dir_error_kind() {
+        let missing = std::env::temp_dir().join(format!("missing_{}", name::process_unique()));
+        let e = TestTempFileBuilder::new().dir(missing).build().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permission_denied_error_kind() {
+        use crate::TestTempDir;
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let d = TestTempDir::new_in_temp_dir(String::from("read_only_dir")).unwrap();
+        // root ignores permission bits
+        if std::fs::metadata(d.path()).unwrap().uid() == 0 {
+            return;
+        }
+        std::fs::set_permissions(d.path(), std::fs::Permissions::from_mode(0o500)).unwrap();
+        let e = TestTempFileBuilder::new().dir(d.path().to_path_buf()).build().unwrap_err();
+        std::fs::set_permissions(d.path(), std::fs::Permissions::from_mode(0o700)).unwrap();
+        assert_eq!(e.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_exclusive() {
+        let taken = TestTempFileBuilder::new().seed(5).build().unwrap();
+        let e = TestTempFileBuilder::new().seed(5).exclusive(true).build().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::AlreadyExists);
+
+        let e = TestTempFileBuilder::new()
+            .exclusive(true)
+            .build_from(1, || taken.random_number)
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::AlreadyExists);
+        assert!(TestTempFileBuilder::new().exclusive(true).build().is_ok());
+    }
+
+    #[test]
+    fn test_newline_mode() {
+        let mut raw = TestTempFileBuilder::new().newline_mode(NewlineMode::Raw).build().unwrap();
+        raw.write_str("a\nb\r\n").unwrap();
+        raw.append_line("c").unwrap();
+        assert_eq!(raw.read_to_vec().unwrap(), b"a\nb\r\nc\n");
+
+        let mut crlf = TestTempFileBuilder::new().newline_mode(NewlineMode::CrlfOnWindows).build().unwrap();
+        crlf.write_str("a\nb\r\n").unwrap();
+        crlf.append_line("c").unwrap();
+        if cfg!(windows) {
+            assert_eq!(crlf.read_to_vec().unwrap(), b"a\r\nb\r\nc\r\n");
+        } else {
+            assert_eq!(crlf.read_to_vec().unwrap(), b"a\nb\r\nc\n");
+        }
+    }
+
+    #[test]
+    fn test_pid_and_time() {
+        let t = TestTempFileBuilder::new()
+            .name(String::from("debug.txt"))
+            .pid_and_time(true)
+            .build()
+            .unwrap();
+        let name = file_name(&t);
+        let pid = std::process::id().to_string();
+        let parts: Vec<&str> = name.trim_end_matches(".txt").split('_').collect();
+        assert_eq!(parts.len(), 4, "{}", name);
+        assert_eq!(parts[0], "debug");
+        assert_eq!(parts[1], pid);
+        assert!(parts[2].parse::<u128>().is_ok(), "{}", name);
+        assert_eq!(parts[3], name::tagged(t.random_number));
+
+        let t = TestTempFileBuilder::new().name(String::from("debug.txt")).build().unwrap();
+        assert_eq!(file_name(&t), format!("debug_{}.txt", name::tagged(t.random_number)));
+    }
+
+    #[test]
+    fn test_randomize_off() {
+        use crate::TestTempDir;
+
+        let d = TestTempDir::new(String::from("verbatim"));
+        let builder = TestTempFileBuilder::new().dir(d.path().to_path_buf()).randomize(false);
+        let mut t = builder.clone().name(String::from("config.toml")).build().unwrap();
+        let path = t.final_filename.clone();
+        assert!(path.ends_with("config.toml"));
+        assert_eq!(path, d.child("config.toml"));
+        t.write_all(b"first").unwrap();
+
+        let e = builder.clone().exclusive(true).name(String::from("config.toml")).build().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::AlreadyExists);
+        let mut reused = builder.clone().name(String::from("config.toml")).build().unwrap();
+        assert!(reused.is_empty().unwrap());
+        reused.write_all(b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        drop(reused);
+        drop(t);
+        assert!(!path.exists());
+
+        let bare = builder.prefix(String::from("report")).suffix(String::from(".csv")).build().unwrap();
+        assert_eq!(file_name(&bare), "report.csv");
+    }
+
+    #[test]
+    fn test_on_drop() {
+        use std::sync::{Arc, Mutex};
+
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&dropped);
+        let t = TestTempFileBuilder::new()
+            .on_drop(move |path| recorded.lock().unwrap().push((path.to_path_buf(), path.exists())))
+            .build()
+            .unwrap();
+        let path = t.final_filename.clone();
+        let clone = t.try_clone().unwrap();
+        drop(clone);
+        assert!(dropped.lock().unwrap().is_empty());
+        drop(t);
+        assert_eq!(*dropped.lock().unwrap(), vec![(path.clone(), true)]);
+        assert!(!path.exists());
+
+        let t = TestTempFileBuilder::new().on_drop(|_| panic!("in the hook")).build().unwrap();
+        let path = t.final_filename.clone();
+        drop(t);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_random_alphabet() {
+        let d = crate::TestTempDir::new(String::from("alphabet"));
+        let builder = TestTempFileBuilder::new()
+            .dir(d.path().to_path_buf())
+            .prefix(String::new())
+            .random_alphabet(RandomAlphabet::AlphaNumeric)
+            .random_len(12);
+        let files: Vec<TestTempFile> = (0..200).map(|_| builder.clone().build().unwrap()).collect();
+        let names: std::collections::HashSet<String> = files.iter().map(file_name).collect();
+        assert_eq!(names.len(), files.len());
+        for name in &names {
+            assert_eq!(name.len(), 12, "{}", name);
+            assert!(name.bytes().all(|b| b.is_ascii_alphanumeric()), "{}", name);
+        }
+
+        let t = TestTempFileBuilder::new().random_alphabet(RandomAlphabet::Hex).build().unwrap();
+        assert_eq!(file_name(&t), format!("_{:x}", t.random_number));
+        let t = TestTempFileBuilder::new().random_len(4).build().unwrap();
+        assert_eq!(file_name(&t), format!("_{:04}", t.random_number % 10_000));
+        let e = TestTempFileBuilder::new().random_len(0).build().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_long_name_is_shortened() {
+        let long = "a".repeat(300);
+        let t = TestTempFileBuilder::new()
+            .dir(std::env::temp_dir())
+            .name(format!("{}.txt", long))
+            .build()
+            .unwrap();
+        let name = file_name(&t);
+        assert!(name.len() <= name::MAX_NAME_LEN, "{}", name.len());
+        assert!(name.ends_with(&format!("a_{}.txt", name::tagged(t.random_number))), "{}", name);
+
+        let e = TestTempFileBuilder::new().suffix(format!(".{}", long)).build().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_build_many() {
+        let files = TestTempFileBuilder::new()
+            .name(String::from("build_many_shards/shard.bin"))
+            .build_many(5)
+            .unwrap();
+        assert_eq!(files.len(), 5);
+        let paths: Vec<PathBuf> = files.iter().map(|t| t.final_filename.clone()).collect();
+        for (index, t) in files.iter().enumerate() {
+            let name = file_name(t);
+            assert!(name.starts_with(&format!("shard_{}_", index)), "{}", name);
+            assert!(name.ends_with(".bin"));
+            assert!(t.final_filename.exists());
+        }
+        let unique: std::collections::HashSet<&PathBuf> = paths.iter().collect();
+        assert_eq!(unique.len(), 5);
+        drop(files);
+        for path in &paths {
+            assert!(!path.exists());
+        }
+        assert!(!Path::new("build_many_shards").exists());
+
+        assert!(TestTempFileBuilder::new().build_many(0).unwrap().is_empty());
+        assert_eq!(TestTempFileBuilder::new().build_many(1).unwrap().len(), 1);
+        let e = TestTempFileBuilder::new().randomize(false).build_many(2).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_keep_on_panic() {
+        let t = TestTempFileBuilder::new().keep_on_panic(true).build().unwrap();
+        let path = t.final_filename.clone();
+        drop(t);
+        assert!(!path.exists());
+
+        let result = std::panic::catch_unwind(|| {
+            let t = TestTempFileBuilder::new().keep_on_panic(true).build().unwrap();
+            let path = t.final_filename.clone();
+            panic!("{}", path.display());
+        });
+        let payload = result.unwrap_err();
+        let path = PathBuf::from(payload.downcast_ref::<String>().unwrap());
+        assert!(path.exists());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_empty_name() {
+        let t = TestTempFileBuilder::new().name(String::new()).build().unwrap();
+        assert_eq!(file_name(&t), format!("tmp_{}", name::tagged(t.random_number)));
+        let t = TestTempFileBuilder::new().name_with_extension("", "txt").build().unwrap();
+        assert_eq!(file_name(&t), format!("tmp_{}.txt", name::tagged(t.random_number)));
+    }
+
+    #[test]
+    fn test_lock_names() {
+        use crate::TestTempDir;
+
+        let d = TestTempDir::new(String::from("lock_names"));
+        let builder = TestTempFileBuilder::new().dir(d.path().to_path_buf()).lock_names(true);
+        let held = d.path().join(format!("{}.lock", builder.file_name(7).unwrap().to_str().unwrap()));
+        std::fs::write(&held, b"").unwrap();
+
+        let mut draws = vec![7, 8].into_iter();
+        let t = builder.clone().build_from(2, || draws.next().unwrap()).unwrap();
+        assert_eq!(t.random_number, 8);
+        let lock = d.path().join(format!("{}.lock", file_name(&t)));
+        assert!(!lock.exists());
+        assert!(held.exists());
+        std::fs::remove_file(&held).unwrap();
+        assert_eq!(std::fs::read_dir(d.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_template() {
+        let builder = TestTempFileBuilder::new()
+            .prefix(String::from("ignored_"))
+            .template(String::from("fixture-{rand}-v2.json"));
+        let t = builder.clone().build_from(1, || 12345).unwrap();
+        assert_eq!(t.final_filename, PathBuf::from("fixture-ttf00000000000000012345-v2.json"));
+        let t = builder.build().unwrap();
+        assert_eq!(file_name(&t), format!("fixture-{}-v2.json", name::tagged(t.random_number)));
+
+        let e = TestTempFileBuilder::new().template(String::from("fixture.json")).build().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_write_only() {
+        let mut t = TestTempFileBuilder::new()
+            .readable(false)
+            .build()
+            .unwrap();
+        t.write_all(b"can't read this back").unwrap();
+        t.flush().unwrap();
+        assert!(t.rewind().is_ok());
+        let mut buf = [0; 4];
+        assert!(t.read(&mut buf).is_err());
+        assert_eq!(std::fs::read(t.path()).unwrap(), b"can't read this back");
+    }
+
+    #[test]
+    fn test_read_only() {
+        let mut t = TestTempFileBuilder::new()
+            .writable(false)
+            .build()
+            .unwrap();
+        assert!(t.write_all(b"nope").is_err());
+        assert!(t.read_to_string().unwrap().is_empty());
+
+        let e = TestTempFileBuilder::new()
+            .readable(false)
+            .writable(false)
+            .build()
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_build_many_on_drop_runs_for_last() {
+        let dropped = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = dropped.clone();
+        let files = TestTempFileBuilder::new()
+            .prefix(String::from("hooked_"))
+            .on_drop(move |path| recorded.lock().unwrap().push(path.to_path_buf()))
+            .build_many(3)
+            .unwrap();
+        let last = files[2].final_filename.clone();
+        assert!(file_name(&files[2]).starts_with("hooked_2_"));
+        drop(files);
+        assert_eq!(*dropped.lock().unwrap(), vec![last]);
+    }
+
+    #[test]
+    fn test_combined() {
+        let t = TestTempFileBuilder::new()
+            .dir(std::env::temp_dir())
+            .prefix(String::from("data_"))
+            .suffix(String::from(".csv"))
+            .keep_on_drop(false)
+            .build()
+            .unwrap();
+        let path = t.final_filename.clone();
+        let name = file_name(&t);
+        assert_eq!(path.parent(), Some(std::env::temp_dir().as_path()));
+        assert!(name.starts_with("data_"));
+        assert!(name.ends_with(".csv"));
+        assert_eq!(t.filename, "data_.csv");
+        drop(t);
+        assert!(!path.exists());
+    }
+}
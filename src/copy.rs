@@ -0,0 +1,115 @@
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+
+/// Copies everything in `from` into `to`, writing from the start of `to`, which should be
+/// empty. Uses `copy_file_range` on Linux with the `fast-copy` feature and falls back to
+/// [`std::io::copy`] when the kernel or filesystem can't do it. The cursor of `from` may
+/// be moved, callers put it back.
+pub(crate) fn copy_file(from: &File, to: &File) -> std::io::Result<u64> {
+    #[cfg(all(feature = "fast-copy", target_os = "linux"))]
+    {
+        if let Some(copied) = copy_file_range(from, to)? {
+            return Ok(copied);
+        }
+    }
+    copy_with_io(from, to)
+}
+
+/// The portable path of [`copy_file`]
+fn copy_with_io(mut from: &File, mut to: &File) -> std::io::Result<u64> {
+    from.seek(SeekFrom::Start(0))?;
+    std::io::copy(&mut from, &mut to)
+}
+
+/// Copies in the kernel without moving either cursor, `None` when that isn't supported
+/// here and nothing was copied yet
+#[cfg(all(feature = "fast-copy", target_os = "linux"))]
+fn copy_file_range(from: &File, to: &File) -> std::io::Result<Option<u64>> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut off_in: libc::loff_t = 0;
+    let mut off_out: libc::loff_t = 0;
+    loop {
+        // both descriptors stay open for the call and the offsets point to live locals
+        let copied = unsafe {
+            libc::copy_file_range(
+                from.as_raw_fd(),
+                &mut off_in,
+                to.as_raw_fd(),
+                &mut off_out,
+                1 << 30,
+                0
+            )
+        };
+        if copied == 0 {
+            // copy_file_range doesn't move the cursor, the io::copy path leaves it at the end
+            let mut to = to;
+            to.seek(SeekFrom::Start(off_out as u64))?;
+            return Ok(Some(off_out as u64));
+        }
+        if copied < 0 {
+            let e = std::io::Error::last_os_error();
+            // EBADF also covers an output opened with O_APPEND, which io::copy handles fine
+            let unsupported = matches!(
+                e.raw_os_error(),
+                Some(libc::EXDEV) | Some(libc::ENOSYS) | Some(libc::EOPNOTSUPP) | Some(libc::EINVAL) | Some(libc::EPERM)
+                    | Some(libc::EBADF) | Some(libc::ETXTBSY)
+            );
+            return match e.kind() {
+                std::io::ErrorKind::Interrupted => continue,
+                _ if unsupported && off_out == 0 => Ok(None),
+                _ => Err(e)
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestTempFile;
+    use std::io::Write;
+
+    fn source() -> TestTempFile {
+        let mut t = TestTempFile::new(String::from("copy_source.bin"));
+        t.preallocate(4 * 1024 * 1024).unwrap();
+        t.write_at(1024 * 1024, b"in the middle").unwrap();
+        t.seek(SeekFrom::End(0)).unwrap();
+        t.write_all(b"at the end").unwrap();
+        t.flush().unwrap();
+        t
+    }
+
+    #[test]
+    fn test_copy_file() {
+        let mut from = source();
+        let mut to = TestTempFile::new(String::from("copy_dest.bin"));
+        let copied = copy_file(from.handle().unwrap().get_ref(), to.handle().unwrap().get_ref()).unwrap();
+        assert_eq!(copied, from.len().unwrap());
+        assert!(from.content_equals(&mut to).unwrap());
+    }
+
+    #[cfg(feature = "fast-copy")]
+    #[test]
+    fn test_copy_file_into_append_mode() {
+        let mut from = source();
+        let mut to = crate::TestTempFileBuilder::new().append(true).build().unwrap();
+        let copied = copy_file(from.handle().unwrap().get_ref(), to.handle().unwrap().get_ref()).unwrap();
+        assert_eq!(copied, from.len().unwrap());
+        assert!(from.content_equals(&mut to).unwrap());
+
+        let mut appending = crate::TestTempFileBuilder::new().append(true).build().unwrap();
+        appending.write_all(b"appended").unwrap();
+        let mut clone = appending.try_clone().unwrap();
+        assert_eq!(clone.read_to_string().unwrap(), "appended");
+    }
+
+    #[test]
+    fn test_copy_with_io() {
+        let mut from = source();
+        let mut to = TestTempFile::new(String::from("copy_dest.bin"));
+        let copied = copy_with_io(from.handle().unwrap().get_ref(), to.handle().unwrap().get_ref()).unwrap();
+        assert_eq!(copied, from.len().unwrap());
+        assert!(from.content_equals(&mut to).unwrap());
+    }
+}
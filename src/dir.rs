@@ -0,0 +1,197 @@
+use crate::{name, TestTempFile, TestTempFileBuilder};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A uniquely named directory that is removed, with everything inside it, on drop.
+///
+/// Files created with [`file`](TestTempDir::file) keep the directory alive, so it's only
+/// removed once it and all of them are dropped, in whatever order that happens.
+/// Nothing is removed while `TEST_TEMP_FILE_KEEP` is set like for a [`TestTempFile`].
+///
+/// # Examples
+/// ```
+/// use test_temp_file::TestTempDir;
+/// let d = TestTempDir::new_in_temp_dir(String::from("scratch")).unwrap();
+/// std::fs::write(d.child("output.txt"), b"some bytes").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct TestTempDir {
+    inner: Arc<DirGuard>
+}
+
+/// Removes the directory once the last [`TestTempDir`] or file referring to it is gone
+#[derive(Debug)]
+struct DirGuard {
+    path: PathBuf
+}
+
+impl Drop for DirGuard {
+    fn drop(&mut self) {
+        if crate::keep_requested() {
+            if self.path.is_dir() {
+                eprintln!("test-temp-file: keeping {} because {} is set", self.path.display(), crate::KEEP_VAR);
+            }
+            return;
+        }
+        match std::fs::remove_dir_all(&self.path) {
+            Ok(()) => (),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => eprintln!("test-temp-file: failed to delete {}: {}", self.path.display(), e)
+        }
+    }
+}
+
+impl TestTempDir {
+    /// Creates the directory as `{dirname}_{random}` in the current working directory,
+    /// panicking on failure.
+    ///
+    /// # Arguments
+    ///
+    /// * `dirname` - A String containing the directory name
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempDir;
+    /// let d = TestTempDir::new(String::from("scratch"));
+    /// assert!(d.path().is_dir());
+    /// ```
+    pub fn new(dirname: String) -> TestTempDir {
+        TestTempDir::try_new(dirname).expect("failed to create test temp dir")
+    }
+
+    /// Same as [`new`](TestTempDir::new), but returns the error from creating the
+    /// directory instead of panicking.
+    pub fn try_new(dirname: String) -> crate::Result<TestTempDir> {
+        TestTempDir::create_in(Path::new(""), &dirname)
+    }
+
+    /// Creates the directory inside the system temp directory instead of the current
+    /// working directory, resolved the same way as
+    /// [`TestTempFile::new_in_temp_dir`](crate::TestTempFile::new_in_temp_dir).
+    pub fn new_in_temp_dir(dirname: String) -> crate::Result<TestTempDir> {
+        TestTempDir::create_in(&crate::temp_dir(), &dirname)
+    }
+
+    fn create_in(parent: &Path, dirname: &str) -> crate::Result<TestTempDir> {
+        name::validate(dirname)?;
+        let (_, path) = name::create_unique(name::MAX_ATTEMPTS, name::process_unique, |random_number| {
+            let path = parent.join(format!("{}_{}", dirname, random_number));
+            std::fs::create_dir(&path).map(|_| path)
+        })?;
+        Ok(TestTempDir { inner: Arc::new(DirGuard { path }) })
+    }
+
+    /// Path of the generated directory
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+
+    /// Creates a [`TestTempFile`] inside the directory, named like
+    /// [`TestTempFile::new`] does. The file holds on to the directory, which is removed
+    /// only after both are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The file name
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempDir;
+    /// let d = TestTempDir::new(String::from("scratch"));
+    /// let t = d.file("input.txt").unwrap();
+    /// assert_eq!(t.parent_dir(), d.path());
+    /// ```
+    pub fn file(&self, name: &str) -> crate::Result<TestTempFile> {
+        let mut t = TestTempFileBuilder::new().dir(self.path().to_path_buf()).name(String::from(name)).build()?;
+        t.owning_dir = Some(TestTempDir { inner: Arc::clone(&self.inner) });
+        Ok(t)
+    }
+
+    /// Leaves the directory on disk if nothing else refers to it
+    pub(crate) fn release(self) {
+        if let Ok(guard) = Arc::try_unwrap(self.inner) {
+            let mut guard = std::mem::ManuallyDrop::new(guard);
+            std::mem::take(&mut guard.path);
+        }
+    }
+
+    /// Path of `name` inside the directory, nothing is created
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempDir;
+    /// let d = TestTempDir::new(String::from("scratch"));
+    /// assert_eq!(d.child("a.txt"), d.path().join("a.txt"));
+    /// ```
+    pub fn child(&self, name: &str) -> PathBuf {
+        self.inner.path.join(name)
+    }
+}
+
+impl AsRef<Path> for TestTempDir {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DIR_NAME: &str = "test_dir";
+
+    #[test]
+    fn test_constructor() {
+        let d = TestTempDir::new(String::from(DIR_NAME));
+        assert!(d.path().is_dir());
+        let name = d.path().file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("test_dir_"));
+    }
+
+    #[test]
+    fn test_new_in_temp_dir() {
+        let d = TestTempDir::new_in_temp_dir(String::from(DIR_NAME)).unwrap();
+        assert!(d.path().starts_with(std::env::temp_dir()));
+    }
+
+    #[test]
+    fn test_missing_parent_error_kind() {
+        let missing = std::env::temp_dir().join(format!("missing_{}", name::process_unique()));
+        let e = TestTempDir::create_in(&missing, DIR_NAME).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_file_drop_order() {
+        let d = TestTempDir::new(String::from(DIR_NAME));
+        let dir = d.path().to_path_buf();
+        let t = d.file("inner.txt").unwrap();
+        let path = t.path().to_path_buf();
+        assert_eq!(path.parent(), Some(dir.as_path()));
+        drop(d);
+        assert!(path.exists());
+        drop(t);
+        assert!(!path.exists());
+        assert!(!dir.exists());
+
+        let d = TestTempDir::new(String::from(DIR_NAME));
+        let dir = d.path().to_path_buf();
+        let t = d.file("inner.txt").unwrap();
+        drop(t);
+        assert!(dir.exists());
+        drop(d);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_drop_removes_tree() {
+        let d = TestTempDir::new_in_temp_dir(String::from(DIR_NAME)).unwrap();
+        let path = d.path().to_path_buf();
+        std::fs::write(d.child("output.txt"), b"some bytes").unwrap();
+        std::fs::create_dir(d.child("nested")).unwrap();
+        std::fs::write(d.child("nested").join("more.txt"), b"more bytes").unwrap();
+        drop(d);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt;
+use std::io::ErrorKind;
+
+/// What went wrong in a fallible call, for matching on specific failures in tests.
+///
+/// It converts to and from [`std::io::Error`], so `?` works in functions returning
+/// `std::io::Result` as well. Errors from the OS become [`TempFileError::Io`].
+///
+/// # Examples
+/// ```
+/// use test_temp_file::{TempFileError, TestTempFile};
+/// let e = TestTempFile::try_new(String::from("nul\0.txt")).unwrap_err();
+/// assert!(matches!(e, TempFileError::InvalidName(_)));
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TempFileError {
+    /// Any other I/O error, returned unchanged from the OS
+    Io(std::io::Error),
+    /// A file name, prefix, suffix or extension that can't be used, with the reason
+    InvalidName(String),
+    /// Every generated name was already taken
+    CollisionExhausted {
+        /// How many names were tried
+        attempts: usize
+    },
+    /// The contents couldn't be serialized to or parsed from JSON
+    #[cfg(feature = "serde")]
+    Serde(serde_json::Error)
+}
+
+/// Result of the crate's fallible calls
+pub type Result<T> = std::result::Result<T, TempFileError>;
+
+impl TempFileError {
+    /// The [`ErrorKind`] the `io::Error` carrying this variant has
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            TempFileError::Io(e) => e.kind(),
+            TempFileError::InvalidName(_) => ErrorKind::InvalidInput,
+            TempFileError::CollisionExhausted { .. } => ErrorKind::AlreadyExists,
+            #[cfg(feature = "serde")]
+            TempFileError::Serde(_) => ErrorKind::InvalidData
+        }
+    }
+}
+
+impl fmt::Display for TempFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TempFileError::Io(e) => write!(f, "{}", e),
+            TempFileError::InvalidName(reason) => write!(f, "{}", reason),
+            TempFileError::CollisionExhausted { attempts } => write!(f, "no unused name found after {} attempts", attempts),
+            #[cfg(feature = "serde")]
+            TempFileError::Serde(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl std::error::Error for TempFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TempFileError::Io(e) => Some(e),
+            #[cfg(feature = "serde")]
+            TempFileError::Serde(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<std::io::Error> for TempFileError {
+    fn from(e: std::io::Error) -> TempFileError {
+        let carries_variant = e.get_ref().is_some_and(|inner| inner.is::<TempFileError>());
+        #[cfg(feature = "serde")]
+        let carries_serde = e.get_ref().is_some_and(|inner| inner.is::<serde_json::Error>());
+        #[cfg(not(feature = "serde"))]
+        let carries_serde = false;
+        if !carries_variant && !carries_serde {
+            return TempFileError::Io(e);
+        }
+        let inner = e.into_inner().expect("checked above");
+        match inner.downcast::<TempFileError>() {
+            Ok(variant) => *variant,
+            #[cfg(feature = "serde")]
+            Err(inner) => TempFileError::Serde(*inner.downcast::<serde_json::Error>().expect("checked above")),
+            #[cfg(not(feature = "serde"))]
+            Err(_) => unreachable!()
+        }
+    }
+}
+
+impl From<TempFileError> for std::io::Error {
+    fn from(e: TempFileError) -> std::io::Error {
+        match e {
+            TempFileError::Io(e) => e,
+            e => std::io::Error::new(e.kind(), e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TestTempFile, TestTempFileBuilder};
+
+    #[test]
+    fn test_invalid_name() {
+        let e = TestTempFile::try_new(String::from("nul\0.txt")).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        let message = e.to_string();
+        match e {
+            TempFileError::InvalidName(reason) => assert_eq!(reason, message),
+            other => panic!("{:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_collision_exhausted() {
+        let t = TestTempFileBuilder::new().seed(11).build().unwrap();
+        let e = TestTempFileBuilder::new().seed(11).build().unwrap_err();
+        assert!(matches!(e, TempFileError::CollisionExhausted { attempts: 1 }));
+        drop(t);
+    }
+
+    #[test]
+    fn test_io_round_trip() {
+        let e = TestTempFile::adopt("does_not_exist.txt".into()).unwrap_err();
+        assert!(matches!(e, TempFileError::Io(_)));
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+        assert_eq!(std::io::Error::from(e).kind(), ErrorKind::NotFound);
+
+        let e = std::io::Error::from(TempFileError::InvalidName(String::from("bad")));
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert!(matches!(TempFileError::from(e), TempFileError::InvalidName(_)));
+
+        let in_io_code = || -> std::io::Result<TestTempFile> { Ok(TestTempFile::try_new(String::from("bad\0.txt"))?) };
+        assert!(matches!(TempFileError::from(in_io_code().unwrap_err()), TempFileError::InvalidName(_)));
+    }
+}
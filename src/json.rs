@@ -0,0 +1,188 @@
+use crate::{TempFileError, TestTempFile};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+
+/// JSON helpers, available with the `serde` feature
+impl TestTempFile {
+    /// Replaces the contents with `value` serialized as JSON
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.json"));
+    /// t.write_json(&vec![1, 2, 3]).unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "[1,2,3]");
+    /// ```
+    pub fn write_json<T: Serialize>(&mut self, value: &T) -> crate::Result<()> {
+        self.truncate()?;
+        serde_json::to_writer(&mut *self, value).map_err(TempFileError::Serde)?;
+        self.flush()?;
+        Ok(())
+    }
+
+    /// Replaces the contents with `value` as pretty-printed JSON and rewinds, for fixtures
+    /// a human reads as well. Unlike [`write_json`](TestTempFile::write_json) the cursor
+    /// ends up at the start.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.json"));
+    /// t.set_json(&vec![1, 2]).unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "[\n  1,\n  2\n]");
+    /// ```
+    pub fn set_json<T: Serialize>(&mut self, value: &T) -> crate::Result<()> {
+        self.truncate()?;
+        serde_json::to_writer_pretty(&mut *self, value).map_err(TempFileError::Serde)?;
+        self.flush()?;
+        self.rewind()
+    }
+
+    /// Deserializes the whole file from JSON, reading from the start
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.json"), b"[1,2,3]").unwrap();
+    /// let value: Vec<u32> = t.read_json().unwrap();
+    /// assert_eq!(value, vec![1, 2, 3]);
+    /// ```
+    pub fn read_json<T: DeserializeOwned>(&mut self) -> crate::Result<T> {
+        serde_json::from_reader(self.rewound_reader()?).map_err(TempFileError::Serde)
+    }
+
+    /// Appends `value` as compact JSON followed by a newline, one record of a JSON lines file
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.jsonl"));
+    /// t.append_json_line(&1).unwrap();
+    /// t.append_json_line(&"two").unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "1\n\"two\"\n");
+    /// ```
+    pub fn append_json_line<T: Serialize>(&mut self, value: &T) -> crate::Result<()> {
+        let mut line = serde_json::to_vec(value).map_err(TempFileError::Serde)?;
+        line.push(b'\n');
+        self.seek(SeekFrom::End(0))?;
+        self.write_all(&line)?;
+        self.flush()?;
+        Ok(())
+    }
+
+    /// Deserializes every line from the start as one JSON value, blank lines are skipped.
+    /// A line that doesn't parse fails with [`ErrorKind::InvalidData`] naming its line number.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.jsonl"), b"1\n2\n").unwrap();
+    /// let values: Vec<u32> = t.read_json_lines().unwrap();
+    /// assert_eq!(values, vec![1, 2]);
+    /// ```
+    pub fn read_json_lines<T: DeserializeOwned>(&mut self) -> crate::Result<Vec<T>> {
+        let mut values = Vec::new();
+        for (index, line) in self.lines()?.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value = serde_json::from_str(&line).map_err(|e| {
+                std::io::Error::new(ErrorKind::InvalidData, format!("line {}: {}", index + 1, e))
+            })?;
+            values.push(value);
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    static FILE_NAME: &str = "test_file.json";
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        name: String,
+        retries: u32,
+        tags: Vec<String>
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let config = Config {
+            name: String::from("parser"),
+            retries: 3,
+            tags: vec![String::from("a"), String::from("b")]
+        };
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"stale content that is longer than the json").unwrap();
+        t.write_json(&config).unwrap();
+        let read: Config = t.read_json().unwrap();
+        assert_eq!(read, config);
+    }
+
+    #[test]
+    fn test_set_json() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.set_json(&Config { name: String::from("first run with a long name"), retries: 10, tags: vec![String::from("slow")] }).unwrap();
+        let latest = Config { name: String::from("second"), retries: 1, tags: Vec::new() };
+        t.set_json(&latest).unwrap();
+        assert_eq!(t.position().unwrap(), 0);
+        let content = t.read_to_string().unwrap();
+        assert_eq!(content, serde_json::to_string_pretty(&latest).unwrap());
+        assert!(!content.contains("first"));
+        let read: Config = t.read_json().unwrap();
+        assert_eq!(read, latest);
+    }
+
+    #[test]
+    fn test_json_lines_round_trip() {
+        let configs: Vec<Config> = (0..3).map(|retries| Config {
+            name: format!("run {}", retries),
+            retries,
+            tags: Vec::new()
+        }).collect();
+        let mut t = TestTempFile::new(String::from("test_file.jsonl"));
+        for config in &configs {
+            t.append_json_line(config).unwrap();
+        }
+        assert_eq!(t.line_count().unwrap(), 3);
+        let read: Vec<Config> = t.read_json_lines().unwrap();
+        assert_eq!(read, configs);
+
+        t.write_all(b"{not json\n").unwrap();
+        let e = t.read_json_lines::<Config>().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert!(e.to_string().starts_with("line 4:"), "{}", e);
+    }
+
+    #[test]
+    fn test_json_dual_cursor() {
+        let mut t = crate::TestTempFileBuilder::new().dual_cursor(true).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_json(&vec![1, 2, 3]).unwrap();
+        for _ in 0..2 {
+            let value: Vec<u32> = t.read_json().unwrap();
+            assert_eq!(value, vec![1, 2, 3]);
+        }
+
+        let mut t = crate::TestTempFileBuilder::new().dual_cursor(true).name(String::from("test_file.jsonl")).build().unwrap();
+        t.append_json_line(&1).unwrap();
+        t.append_json_line(&2).unwrap();
+        for _ in 0..2 {
+            let values: Vec<u32> = t.read_json_lines().unwrap();
+            assert_eq!(values, vec![1, 2]);
+        }
+    }
+
+    #[test]
+    fn test_read_json_invalid() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"{not json").unwrap();
+        let e = t.read_json::<Config>().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert!(matches!(e, TempFileError::Serde(_)));
+    }
+}
@@ -2,55 +2,236 @@
 //! This is aimed mostly for testing purposes, for example when testing a parser you probably
 //! want to read/write file and validate their content
 
-use rand::Rng;
-use std::io::Read;
-use std::path::Path;
+mod builder;
+mod copy;
+mod dir;
+mod error;
+#[cfg(feature = "locking")]
+mod lock;
+mod memory;
+mod name;
+mod reserved;
+mod set;
+mod shared;
+mod split;
+#[cfg(feature = "tokio")]
+mod async_file;
+#[cfg(feature = "serde")]
+mod json;
+
+pub use builder::{NewlineMode, RandomAlphabet, TestTempFileBuilder};
+pub use dir::TestTempDir;
+pub use error::{Result, TempFileError};
+pub use memory::InMemoryTempFile;
+pub use reserved::ReservedTempPath;
+pub use set::TempFileSet;
+pub use shared::SharedTestTempFile;
+pub use split::{TempReader, TempWriter};
+#[cfg(feature = "tokio")]
+pub use async_file::AsyncTestTempFile;
+
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
 use std::io::Write;
-use std::fs::OpenOptions;
+use std::io::ErrorKind;
 use std::io::SeekFrom;
 use std::io::Seek;
+use std::fmt;
+use std::convert::TryFrom;
 
-#[derive(Debug)]
+/// A file with a unique random name that is deleted when dropped.
+///
+/// `TestTempFile` is `Send + Sync` and files can be created from many threads at once,
+/// names are drawn from the full 64 bits and one that's already taken is never reused.
+///
+/// Setting the environment variable `TEST_TEMP_FILE_KEEP` to anything but `0` keeps every
+/// file that would be deleted on drop and prints its path to stderr, for looking at what a
+/// failing CI run left behind without changing code.
+#[allow(dead_code)]
 pub struct TestTempFile {
     filename: String,
-    random_number: i32,
-    final_filename: String,
-    file: std::fs::File
+    random_number: u64,
+    final_filename: PathBuf,
+    file: Option<std::io::BufWriter<std::fs::File>>,
+    reader: Option<std::fs::File>,
+    created_dirs: Vec<PathBuf>,
+    symlinks: std::sync::Mutex<Vec<PathBuf>>,
+    delete_on_drop: bool,
+    commit_target: Option<PathBuf>,
+    // contents read by as_bytes, cleared whenever the handle is borrowed mutably
+    cached_contents: Option<Vec<u8>>,
+    builder: TestTempFileBuilder,
+    // directory the file was created in by new_in_fresh_dir or TestTempDir::file,
+    // dropped after the file is deleted so it outlives the file
+    owning_dir: Option<TestTempDir>
 }
 
 impl Drop for TestTempFile {
     fn drop(&mut self) {
-        self.delete_file();
+        self.builder.run_on_drop(&self.final_filename);
+        if self.builder.get_size_limit().is_some() && self.file.is_some() {
+            let _ = self.flush();
+            if let Err(e) = self.check_size_limit() {
+                eprintln!("test-temp-file: warning: {}", e);
+            }
+        }
+        if let Some(target) = self.commit_target.take() {
+            let _ = self.sync();
+            match move_file(&self.final_filename, &target) {
+                Ok(()) => {
+                    self.remove_symlinks();
+                    builder::remove_dirs(&self.created_dirs);
+                    return;
+                },
+                Err(e) => eprintln!("test-temp-file: failed to commit {} to {}: {}", self.final_filename.display(), target.display(), e)
+            }
+        }
+        let panicking = self.builder.get_keep_on_panic() && std::thread::panicking();
+        if self.delete_on_drop && !keep_requested() && !panicking {
+            self.delete_file();
+        } else {
+            if self.delete_on_drop && panicking {
+                eprintln!("test-temp-file: keeping {} because the thread panicked", self.final_filename.display());
+            } else if self.delete_on_drop {
+                eprintln!("test-temp-file: keeping {} because {} is set", self.final_filename.display(), KEEP_VAR);
+            }
+            let _ = self.sync();
+            if self.final_filename.exists() {
+                if let Some(dir) = self.owning_dir.take() {
+                    dir.release();
+                }
+            }
+        }
     }
 }
 
 impl Write for TestTempFile {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.file.write(buf)
+        self.handle_mut()?.write(buf)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        self.file.flush()
+        self.handle_mut()?.flush()
     }
 
     fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
-        self.file.write_all(buf)
+        write_all_retrying(self.handle_mut()?, buf)
     }
 }
 
 impl Read for TestTempFile {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.file.read(buf)
+        if self.builder.get_flush_before_read() {
+            self.flush()?;
+        }
+        loop {
+            let read = match self.reader {
+                Some(ref mut reader) => reader.read(buf),
+                None => self.handle_mut()?.get_mut().read(buf)
+            };
+            match read {
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                read => return read
+            }
+        }
     }
 }
 
 impl Seek for TestTempFile {
-    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> { self.file.seek(pos) }
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> { self.handle_mut()?.seek(pos) }
+}
+
+impl std::convert::TryFrom<&str> for TestTempFile {
+    type Error = TempFileError;
+
+    fn try_from(name: &str) -> Result<TestTempFile> {
+        TestTempFile::try_new(String::from(name))
+    }
+}
+
+impl fmt::Debug for TestTempFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("TestTempFile");
+        debug.field("path", &self.final_filename);
+        match self.len() {
+            Ok(len) => debug.field("len", &len),
+            Err(e) => debug.field("len", &e)
+        };
+        debug.finish()
+    }
+}
+
+impl fmt::Display for TestTempFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.final_filename.display())
+    }
+}
+
+impl AsRef<Path> for TestTempFile {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+/// Iterator over the lines of a [`TestTempFile`] from the start, returned by `for line in &mut t`.
+/// If the file couldn't be rewound that error is the only item.
+pub struct Lines<'a> {
+    lines: Option<std::io::Lines<BufReader<&'a mut std::fs::File>>>,
+    error: Option<std::io::Error>
+}
+
+impl Iterator for Lines<'_> {
+    type Item = std::io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.error.take() {
+            Some(e) => Some(Err(e)),
+            None => self.lines.as_mut()?.next()
+        }
+    }
+}
+
+/// Writer returned by [`TestTempFile::tee`], every byte the file accepts is written to the
+/// second sink as well
+struct Tee<'a, W> {
+    file: &'a mut TestTempFile,
+    other: W
+}
+
+impl<W: Write> Write for Tee<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.file.write(buf)?;
+        self.other.write_all(&buf[..n])?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        self.other.flush()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut TestTempFile {
+    type Item = std::io::Result<String>;
+    type IntoIter = Lines<'a>;
+
+    fn into_iter(self) -> Lines<'a> {
+        match self.rewound_reader() {
+            Ok(file) => Lines { lines: Some(BufReader::new(file).lines()), error: None },
+            Err(e) => Lines { lines: None, error: Some(e) }
+        }
+    }
 }
 
 impl TestTempFile {
+    /// Creates the file in the current working directory, panicking on failure.
     ///
-    ///  /// # Arguments
+    /// The random component is placed before the extension, so `data.json` becomes
+    /// `data_{random}.json` and a name without an extension becomes `{name}_{random}`.
+    /// An empty name becomes `tmp_{random}`.
+    /// Directories in `filename` are created if missing and removed again on drop if empty.
+    ///
+    /// # Arguments
     ///
     /// * `filename` - A String containing the file name
     ///
@@ -60,80 +241,4014 @@ impl TestTempFile {
     /// let mut t = TestTempFile::new(String::from("file_name.txt"));
     /// ```
     pub fn new(filename: String) -> TestTempFile {
-        TestTempFile::gen_random_name(filename)
+        TestTempFile::try_new(filename).expect("failed to create test temp file")
     }
 
-    fn gen_random_name(filename: String) -> TestTempFile {
-        let mut rng = rand::thread_rng();
-        let random_number = rng.gen_range(0, i32::max_value());
-        let final_filename = format!("_{}_{}", random_number, filename);
-        let file = OpenOptions::new().
-            create(true).
-            write(true).
-            read(true).
-            open(final_filename.clone()).unwrap();
-        TestTempFile {
-            filename,
-            random_number,
-            final_filename,
-            file
-        }
+    /// Same as [`new`](TestTempFile::new), but returns the error from opening the file
+    /// instead of panicking.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::try_new(String::from("file_name.txt")).unwrap();
+    /// ```
+    pub fn try_new(filename: String) -> Result<TestTempFile> {
+        TestTempFileBuilder::new().name(filename).build()
     }
 
-    fn delete_file(&mut self) {
-        let final_filename = self.final_filename.as_str();
-        if Path::new(final_filename).exists() {
-            std::fs::remove_file(final_filename);
+    /// Same as [`try_new`](TestTempFile::try_new) for names that aren't guaranteed to be UTF-8,
+    /// such as ones taken from a [`Path`]. The created path keeps the exact bytes of
+    /// `filename`, nothing is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The file name, directories in it are created if missing
+    ///
+    /// # Examples
+    /// ```
+    /// use std::path::Path;
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_os(Path::new("file_name.txt")).unwrap();
+    /// ```
+    pub fn new_os<S: AsRef<std::ffi::OsStr>>(filename: S) -> Result<TestTempFile> {
+        match filename.as_ref().to_str() {
+            Some(filename) => TestTempFile::try_new(String::from(filename)),
+            None => TestTempFileBuilder::new().name_os(filename.as_ref()).build()
         }
     }
-}
 
+    /// Same as [`try_new`](TestTempFile::try_new) with `test_name` in front, as
+    /// `{test_name}__{stem}_{random}.{ext}`, so a leaked file shows which test created it.
+    /// Path separators and `:` in `test_name` are replaced by `_`, so `module_path!()`
+    /// style names work as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `test_name` - Name of the test, usually the test function's
+    /// * `filename` - A String containing the file name
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::for_test("parses_header", String::from("input.txt")).unwrap();
+    /// assert!(t.path().to_str().unwrap().starts_with("parses_header__input_"));
+    /// ```
+    pub fn for_test(test_name: &str, filename: String) -> Result<TestTempFile> {
+        let tag: String = test_name.chars().
+            map(|c| if std::path::is_separator(c) || c == ':' { '_' } else { c }).
+            collect();
+        let filename = match filename.rfind(std::path::is_separator) {
+            Some(index) => format!("{}{}__{}", &filename[..=index], tag, &filename[index + 1..]),
+            None => format!("{}__{}", tag, filename)
+        };
+        TestTempFile::try_new(filename)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    
-    use std::str;
+    /// Sets the directory files created on the current thread end up in when no directory
+    /// is given, instead of the current working directory, for test harnesses that give
+    /// every test its own place. Affects [`new`](TestTempFile::new) and every other
+    /// constructor taking a relative name, as well as [`TestTempFileBuilder::new`] and
+    /// `AsyncTestTempFile::new`, which picks it up before handing off to a blocking thread.
+    /// `None` goes back to the working directory. Other threads aren't affected.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory, which must exist, or `None` to clear it
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// TestTempFile::set_thread_default_dir(Some(std::env::temp_dir()));
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// assert!(t.path().starts_with(std::env::temp_dir()));
+    /// TestTempFile::set_thread_default_dir(None);
+    /// ```
+    pub fn set_thread_default_dir(dir: Option<PathBuf>) {
+        builder::set_default_dir(dir);
+    }
 
-    static FILE_NAME: &'static str = "test_file.txt";
+    /// Same as [`new`](TestTempFile::new) for a `&str`, panicking on failure.
+    /// `TryFrom<&str>` is implemented as well for the fallible variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The file name
+    ///
+    /// # Examples
+    /// ```
+    /// use std::convert::TryInto;
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::named("file_name.txt");
+    /// let u: TestTempFile = "file_name.txt".try_into().unwrap();
+    /// ```
+    pub fn named(name: &str) -> TestTempFile {
+        TestTempFile::new(String::from(name))
+    }
 
-    #[test]
-    fn test_constructor() {
-        let t = TestTempFile::new(String::from(FILE_NAME));
-        assert_eq!(FILE_NAME, t.filename);
+    /// Creates the file in the current working directory as `{stem}_{random}.{ext}`, without
+    /// guessing where the extension starts, so dots in `stem` stay where they are.
+    /// An empty `ext` gives `{stem}_{random}` without a dot.
+    ///
+    /// # Arguments
+    ///
+    /// * `stem` - The file name before the extension, may include directories
+    /// * `ext` - The extension without the leading dot
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_with_extension("report.v2", "csv").unwrap();
+    /// assert_eq!(t.path().extension().unwrap(), "csv");
+    /// ```
+    pub fn new_with_extension(stem: &str, ext: &str) -> Result<TestTempFile> {
+        TestTempFileBuilder::new().name_with_extension(stem, ext).build()
     }
 
-    #[test]
-    fn test_write() {
-        let mut t = TestTempFile::new(String::from(FILE_NAME));
-        match t.write_all(b"some bytes") {
-            Ok(_) => assert!(true),
-            Err(e) => assert!(false, e)
-        }
+    /// Creates the file inside the system temp directory instead of the current working
+    /// directory. That's `$TMPDIR` on unix, falling back to `/tmp`, and on Windows the first
+    /// of `%TMP%`, `%TEMP%` and `%USERPROFILE%` that is set, see [`std::env::temp_dir`].
+    /// A directory passed to [`TestTempFileBuilder::dir`] is used regardless of these.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_in_temp_dir(String::from("file_name.txt")).unwrap();
+    /// ```
+    pub fn new_in_temp_dir(filename: String) -> Result<TestTempFile> {
+        TestTempFileBuilder::new().dir(temp_dir()).name(filename).build()
     }
 
-    #[test]
-    fn test_write_and_read() {
-        let mut t = TestTempFile::new(String::from(FILE_NAME));
-        let mut buffer = [0; 10];
-        let msg = b"some bytes";
-        match t.write_all(msg) {
-            Ok(_) => assert!(true),
-            Err(e) => assert!(false, e)
+    /// Creates the file in `mount_dir`, typically a directory on another filesystem for
+    /// reproducing cross-device cases like `EXDEV` on rename. Fails with
+    /// [`ErrorKind::NotFound`] naming `mount_dir` if it doesn't exist or isn't a directory,
+    /// nothing is created on the way.
+    ///
+    /// # Arguments
+    ///
+    /// * `mount_dir` - An existing directory, for example the mount point of a tmpfs
+    /// * `filename` - A String containing the file name
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::on_mount(&std::env::temp_dir(), String::from("file_name.txt")).unwrap();
+    /// assert!(t.path().starts_with(std::env::temp_dir()));
+    /// ```
+    pub fn on_mount(mount_dir: &Path, filename: String) -> Result<TestTempFile> {
+        if !mount_dir.is_dir() {
+            let message = format!("mount directory {} doesn't exist or isn't a directory", mount_dir.display());
+            return Err(std::io::Error::new(ErrorKind::NotFound, message).into());
         }
+        TestTempFileBuilder::new().dir(mount_dir.to_path_buf()).name(filename).build()
+    }
 
-        // Need to rewind pointer inside file, since after the write we're pointing to the end
+    /// Picks a unique path the way [`try_new`](TestTempFile::try_new) does, but leaves
+    /// creating the file to the caller. Only the missing directories are created, the name
+    /// is free when it's picked but nothing holds it until the caller creates the file.
+    /// Whatever is at the path when the returned guard is dropped is deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let reserved = TestTempFile::reserve(String::from("file_name.txt")).unwrap();
+    /// std::fs::write(reserved.path(), b"made elsewhere").unwrap();
+    /// ```
+    pub fn reserve(filename: String) -> Result<ReservedTempPath> {
+        let (path, created_dirs) = TestTempFileBuilder::new().name(filename).reserve()?;
+        Ok(ReservedTempPath::new(path, created_dirs))
+    }
 
-        t.seek(SeekFrom::Start(0));
+    /// Creates a randomly named file in the system temp directory that's deleted on drop,
+    /// the counterpart of `tempfile::NamedTempFile::new` for tests moving over from the
+    /// `tempfile` crate. [`path`](TestTempFile::path) and [`persist`](TestTempFile::persist)
+    /// cover the rest of the usual `NamedTempFile` workflow.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::named_temp().unwrap();
+    /// t.write_all(b"some bytes").unwrap();
+    /// assert!(t.path().starts_with(std::env::temp_dir()));
+    /// ```
+    pub fn named_temp() -> Result<TestTempFile> {
+        TestTempFileBuilder::new().dir(temp_dir()).build()
+    }
 
-        match t.read(&mut buffer[..]) {
-            Ok(n) => {
-                assert_eq!(msg,
-                           &buffer[..n],
-                           "Left:{:#?}\nRight:{:#?}\n{:#?}",
-                           msg, &buffer[..n], t)
-            },
-            Err(e) => assert!(false, format!("Error:{}\n{:#?}", e.to_string(), t))
-        }
+    /// Creates the file in `subdir` of the package being tested, next to fixtures such as
+    /// golden files. The package directory is read from `CARGO_MANIFEST_DIR`, which cargo
+    /// sets when running tests. `subdir` is created if missing and removed on drop if empty.
+    /// Fails with [`ErrorKind::NotFound`] when `CARGO_MANIFEST_DIR` isn't set.
+    ///
+    /// # Arguments
+    ///
+    /// * `subdir` - Directory relative to the package root, e.g. `tests/fixtures`
+    /// * `filename` - A String containing the file name
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_in_manifest_subdir("target", String::from("output.txt")).unwrap();
+    /// assert!(t.path().starts_with(env!("CARGO_MANIFEST_DIR")));
+    /// ```
+    pub fn new_in_manifest_subdir(subdir: &str, filename: String) -> Result<TestTempFile> {
+        let manifest_dir = match std::env::var_os("CARGO_MANIFEST_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => return Err(std::io::Error::new(ErrorKind::NotFound, "CARGO_MANIFEST_DIR isn't set").into())
+        };
+        let filename = Path::new(subdir).join(filename);
+        TestTempFileBuilder::new().dir(manifest_dir).name(filename.to_string_lossy().into_owned()).build()
+    }
+
+    /// Creates the file and immediately removes its name, so it never shows up in directory
+    /// listings while the handle stays usable for reading and writing. The data is gone as
+    /// soon as the handle is closed, nothing is left to clean up. [`path`](TestTempFile::path)
+    /// still returns the name the file had, anything opening it by path fails.
+    ///
+    /// On Windows the name may stay visible until the handle is closed.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_anonymous(String::from("secret.txt")).unwrap();
+    /// t.write_all(b"never on disk by name").unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "never on disk by name");
+    /// # #[cfg(unix)]
+    /// assert!(!t.path().exists());
+    /// ```
+    pub fn new_anonymous(filename: String) -> Result<TestTempFile> {
+        let mut t = TestTempFile::try_new(filename)?;
+        remove_file(&t.final_filename)?;
+        t.delete_on_drop = false;
+        Ok(t)
+    }
+
+    /// Creates the file inside a new, uniquely named directory in the system temp directory.
+    /// On drop the file is deleted and then the directory, along with anything else the
+    /// test left in it. When the file is kept the directory is kept too.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_in_fresh_dir(String::from("file_name.txt")).unwrap();
+    /// let dir = t.path().parent().unwrap().to_path_buf();
+    /// drop(t);
+    /// assert!(!dir.exists());
+    /// ```
+    pub fn new_in_fresh_dir(filename: String) -> Result<TestTempFile> {
+        let dir = TestTempDir::new_in_temp_dir(String::from("test_temp_file"))?;
+        let mut t = TestTempFileBuilder::new().dir(dir.path().to_path_buf()).name(filename).build()?;
+        t.owning_dir = Some(dir);
+        Ok(t)
+    }
+
+    #[doc(hidden)]
+    #[deprecated(note = "renamed back to `new_in_fresh_dir`")]
+    pub fn new_in_owning_dir(filename: String) -> Result<TestTempFile> {
+        TestTempFile::new_in_fresh_dir(filename)
+    }
+
+    /// Creates the file with `content` already written and the cursor back at the start,
+    /// ready to be handed to the code under test.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    /// * `content` - The bytes the file starts with
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"hello").unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "hello");
+    /// ```
+    pub fn new_with_content(filename: String, content: &[u8]) -> Result<TestTempFile> {
+        let mut t = TestTempFile::try_new(filename)?;
+        t.write_all(content)?;
+        t.flush()?;
+        t.rewind()?;
+        Ok(t)
+    }
+
+    /// Creates the file and streams everything from `reader` into it, leaving the cursor
+    /// at the start. The input is copied in chunks, never loaded into memory at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    /// * `reader` - Source of the contents
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut source = std::io::Cursor::new(b"streamed".to_vec());
+    /// let mut t = TestTempFile::from_reader(String::from("file_name.txt"), &mut source).unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "streamed");
+    /// ```
+    pub fn from_reader<R: Read>(filename: String, reader: &mut R) -> Result<TestTempFile> {
+        let mut t = TestTempFile::try_new(filename)?;
+        std::io::copy(reader, &mut t)?;
+        t.flush()?;
+        t.rewind()?;
+        Ok(t)
+    }
+
+    /// Creates the file with a copy of the contents of `src`, leaving the cursor at the
+    /// start, for mutating a committed fixture without touching the original. Fails with
+    /// [`ErrorKind::NotFound`] before creating anything if `src` doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    /// * `src` - The file to copy, it's only read
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::from_path_copy(String::from("manifest.toml"), "Cargo.toml").unwrap();
+    /// assert!(t.read_to_string().unwrap().starts_with("[package]"));
+    /// ```
+    pub fn from_path_copy<P: AsRef<Path>>(filename: String, src: P) -> Result<TestTempFile> {
+        let source = std::fs::File::open(src)?;
+        let mut t = TestTempFile::try_new(filename)?;
+        copy::copy_file(&source, t.handle()?.get_ref())?;
+        t.rewind()?;
+        Ok(t)
+    }
+
+    /// Creates the file like [`try_new`](TestTempFile::try_new) but opened with `options`,
+    /// see [`TestTempFileBuilder::options`]
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    /// * `options` - How the file is opened, `write` and `create_new` are added
+    ///
+    /// # Examples
+    /// ```
+    /// use std::fs::OpenOptions;
+    /// use test_temp_file::TestTempFile;
+    /// let mut options = OpenOptions::new();
+    /// options.read(true).append(true);
+    /// let t = TestTempFile::with_options(String::from("file_name.txt"), options).unwrap();
+    /// ```
+    pub fn with_options(filename: String, options: std::fs::OpenOptions) -> Result<TestTempFile> {
+        TestTempFileBuilder::new().options(options).name(filename).build()
+    }
+
+    /// Takes over an existing file, for example one written by an external tool, so it's
+    /// deleted on drop like any other. The file keeps its name and contents, the cursor
+    /// starts at the beginning. Fails with [`ErrorKind::NotFound`] if there's no file at `path`.
+    /// [`random_id`](TestTempFile::random_id) is 0 since there's no random component.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to manage
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let path = std::env::temp_dir().join("tool_output.txt");
+    /// std::fs::write(&path, b"from the tool").unwrap();
+    /// let mut t = TestTempFile::adopt(path.clone()).unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "from the tool");
+    /// drop(t);
+    /// assert!(!path.exists());
+    /// ```
+    pub fn adopt(path: PathBuf) -> Result<TestTempFile> {
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        Ok(TestTempFileBuilder::new().dir(dir).name(name).adopt(path)?)
+    }
+
+    /// Creates the file in the current working directory with the random component drawn
+    /// from a generator seeded with `seed`, so the same seed and filename always produce
+    /// the same path. Fails with [`ErrorKind::AlreadyExists`] if that path is in use.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    /// * `seed` - Seed for the random component
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_seeded(String::from("file_name.txt"), 7).unwrap();
+    /// let path = t.path().to_path_buf();
+    /// drop(t);
+    /// let t = TestTempFile::new_seeded(String::from("file_name.txt"), 7).unwrap();
+    /// assert_eq!(t.path(), path);
+    /// ```
+    pub fn new_seeded(filename: String, seed: u64) -> Result<TestTempFile> {
+        TestTempFileBuilder::new().name(filename).seed(seed).build()
+    }
+
+    /// Creates the file in the current working directory with the random component drawn
+    /// from `rng`, for example a seeded `StdRng` to get the same names on every run. When a
+    /// name is taken the next number from `rng` is tried. Requires the `rand` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    /// * `rng` - Generator the random component comes from
+    ///
+    /// # Examples
+    /// ```
+    /// use rand::SeedableRng;
+    /// use rand::rngs::StdRng;
+    /// use test_temp_file::TestTempFile;
+    /// let mut rng = StdRng::seed_from_u64(2024);
+    /// let t = TestTempFile::new_with_rng(String::from("file_name.txt"), &mut rng).unwrap();
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn new_with_rng<R: rand::Rng + ?Sized>(filename: String, rng: &mut R) -> Result<TestTempFile> {
+        TestTempFileBuilder::new().name(filename).build_with(|| rng.random())
     }
-}
\ No newline at end of file
+
+    /// Creates the file, runs `f` with it and deletes it again before returning. The file
+    /// is also deleted when `f` panics, since it's dropped while unwinding.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - A String containing the file name
+    /// * `f` - Closure that gets to use the file
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let path = TestTempFile::with(String::from("file_name.txt"), |t| {
+    ///     t.write_all(b"some bytes").unwrap();
+    ///     t.path().to_path_buf()
+    /// }).unwrap();
+    /// assert!(!path.exists());
+    /// ```
+    pub fn with<F, T>(filename: String, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut TestTempFile) -> T,
+    {
+        let mut t = TestTempFile::try_new(filename)?;
+        Ok(f(&mut t))
+    }
+
+    /// Returns a [`TestTempFileBuilder`] for configuring the directory, name and
+    /// deletion behavior of the file.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::builder().suffix(String::from(".json")).build().unwrap();
+    /// ```
+    pub fn builder() -> TestTempFileBuilder {
+        TestTempFileBuilder::new()
+    }
+
+    /// The random number in the file name, printed as 20 zero-padded decimal digits after
+    /// `ttf` unless another [`RandomAlphabet`] was chosen. 0 for names built with
+    /// [`randomize(false)`](TestTempFileBuilder::randomize).
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// assert!(t.path().to_str().unwrap().contains(&t.random_id().to_string()));
+    /// ```
+    pub fn random_id(&self) -> u64 {
+        self.random_number
+    }
+
+    /// Directory the file is in, an empty path when it's in the current working directory
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_in_temp_dir(String::from("file_name.txt")).unwrap();
+    /// assert_eq!(t.parent_dir(), std::env::temp_dir());
+    /// ```
+    pub fn parent_dir(&self) -> &Path {
+        self.final_filename.parent().unwrap_or_else(|| Path::new(""))
+    }
+
+    /// Paths of the other files in the same directory, for checking what the code under
+    /// test created next to the file. Directories and the file itself aren't included.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_in_fresh_dir(String::from("data.dat")).unwrap();
+    /// std::fs::write(t.parent_dir().join("data.idx"), b"index").unwrap();
+    /// assert_eq!(t.sibling_entries().unwrap(), vec![t.parent_dir().join("data.idx")]);
+    /// ```
+    pub fn sibling_entries(&self) -> Result<Vec<PathBuf>> {
+        let dir = self.parent_dir();
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        let own_name = self.final_filename.file_name();
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() || Some(entry.file_name().as_os_str()) == own_name {
+                continue;
+            }
+            entries.push(self.parent_dir().join(entry.file_name()));
+        }
+        entries.sort();
+        Ok(entries)
+    }
+
+    /// Path of the generated file, exactly as it was used to open it
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// assert!(t.path().exists());
+    /// ```
+    pub fn path(&self) -> &Path {
+        &self.final_filename
+    }
+
+    /// Consumes the `TestTempFile` without deleting the file and returns its path,
+    /// useful for inspecting the contents after a failing test.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// let path = t.keep();
+    /// assert!(path.exists());
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    pub fn keep(mut self) -> PathBuf {
+        self.delete_on_drop = false;
+        self.final_filename.clone()
+    }
+
+    /// Keeps the file if `pred` returns `true` for its contents and returns its path,
+    /// otherwise deletes it like a drop would and returns `None`. Useful for keeping only
+    /// the interesting artifacts, for example logs that mention a panic.
+    ///
+    /// # Arguments
+    ///
+    /// * `pred` - Decides from the contents whether the file is kept
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_with_content(String::from("file_name.log"), b"all good").unwrap();
+    /// let kept = t.retain_if(|content| content.windows(5).any(|w| w == b"PANIC")).unwrap();
+    /// assert!(kept.is_none());
+    /// ```
+    pub fn retain_if<F: FnOnce(&[u8]) -> bool>(mut self, pred: F) -> Result<Option<PathBuf>> {
+        let content = self.read_to_vec()?;
+        if pred(&content) {
+            Ok(Some(self.keep()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Flushes and waits until the contents and metadata are on disk,
+    /// see [`std::fs::File::sync_all`]. This also happens on drop whenever the file is kept.
+    pub fn sync(&mut self) -> Result<()> {
+        if self.file.is_none() {
+            return Ok(());
+        }
+        self.flush()?;
+        Ok(self.handle()?.get_ref().sync_all()?)
+    }
+
+    /// Flushes and waits until the contents and metadata are on disk,
+    /// see [`std::fs::File::sync_all`]. Same as [`sync`](TestTempFile::sync).
+    pub fn sync_all(&mut self) -> Result<()> {
+        self.sync()
+    }
+
+    /// Flushes and waits until the contents are on disk, skipping metadata that isn't
+    /// needed to read them back, see [`std::fs::File::sync_data`]
+    pub fn sync_data(&mut self) -> Result<()> {
+        if self.file.is_none() {
+            return Ok(());
+        }
+        self.flush()?;
+        Ok(self.handle()?.get_ref().sync_data()?)
+    }
+
+    /// Opens a second, read-only handle to the file with its own cursor starting at 0.
+    /// It only sees what was written through this `TestTempFile` after a flush.
+    ///
+    /// On Windows this works because std opens files with read, write and delete sharing
+    /// enabled, handles opened with other sharing modes may block it.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"some bytes").unwrap();
+    /// t.flush().unwrap();
+    /// let mut content = String::new();
+    /// t.reopen_readonly().unwrap().read_to_string(&mut content).unwrap();
+    /// assert_eq!(content, "some bytes");
+    /// ```
+    pub fn reopen_readonly(&self) -> Result<std::fs::File> {
+        Ok(std::fs::File::open(&self.final_filename)?)
+    }
+
+    /// Length of the file in bytes as the OS currently sees it, the cursor isn't moved.
+    /// Writes still held by [`TestTempFileBuilder::buffered`] aren't counted, use
+    /// [`flushed_len`](TestTempFile::flushed_len) to include them.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"some bytes").unwrap();
+    /// assert_eq!(t.len().unwrap(), 10);
+    /// ```
+    pub fn len(&self) -> Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    /// Flushes buffered writes and then returns the length of the file, so unlike
+    /// [`len`](TestTempFile::len) everything written so far is counted. The cursor isn't moved.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::builder().buffered(1024).build().unwrap();
+    /// t.write_all(b"some bytes").unwrap();
+    /// assert_eq!(t.len().unwrap(), 0);
+    /// assert_eq!(t.flushed_len().unwrap(), 10);
+    /// ```
+    pub fn flushed_len(&mut self) -> Result<u64> {
+        self.flush()?;
+        self.len()
+    }
+
+    /// Fails if the file is larger than the limit set with
+    /// [`TestTempFileBuilder::warn_if_larger_than`], without a limit it always succeeds.
+    /// Buffered writes that weren't flushed yet aren't counted.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::builder().warn_if_larger_than(1024).build().unwrap();
+    /// t.preallocate(4096).unwrap();
+    /// assert!(t.check_size_limit().is_err());
+    /// ```
+    pub fn check_size_limit(&self) -> Result<()> {
+        let limit = match self.builder.get_size_limit() {
+            Some(limit) => limit,
+            None => return Ok(())
+        };
+        let len = self.len()?;
+        if len <= limit {
+            return Ok(());
+        }
+        Err(std::io::Error::other(format!(
+            "{} is {} bytes, larger than the limit of {} bytes",
+            self.final_filename.display(),
+            len,
+            limit
+        )).into())
+    }
+
+    /// Same as [`len`](TestTempFile::len), reads better next to
+    /// [`line_count`](TestTempFile::line_count) in assertions
+    pub fn byte_count(&self) -> Result<u64> {
+        self.len()
+    }
+
+    /// Number of lines in the file, a last line without a trailing newline is counted too.
+    /// The cursor is left where it was.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"a\nb\n").unwrap();
+    /// assert_eq!(t.line_count().unwrap(), 2);
+    /// ```
+    pub fn line_count(&mut self) -> Result<usize> {
+        self.with_rewound(|t| {
+            let mut count = 0;
+            for line in BufReader::new(t.handle_mut()?.get_mut()).split(b'\n') {
+                line?;
+                count += 1;
+            }
+            Ok(count)
+        })
+    }
+
+    /// Metadata of the open file, see [`std::fs::File::metadata`].
+    /// After [`close`](TestTempFile::close) it's looked up by path.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// assert!(t.metadata().unwrap().is_file());
+    /// ```
+    pub fn metadata(&self) -> Result<std::fs::Metadata> {
+        let metadata = match self.file {
+            Some(ref file) => file.get_ref().metadata()?,
+            None => std::fs::metadata(&self.final_filename)?
+        };
+        Ok(metadata)
+    }
+
+    /// Sets the modification time of the file, see [`std::fs::File::set_modified`].
+    /// Any later write updates it again, so write and flush the contents first.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// let last_week = SystemTime::now() - Duration::from_secs(7 * 24 * 60 * 60);
+    /// t.set_mtime(last_week).unwrap();
+    /// assert!(t.metadata().unwrap().modified().unwrap() < SystemTime::now());
+    /// ```
+    pub fn set_mtime(&self, time: std::time::SystemTime) -> Result<()> {
+        Ok(self.handle()?.get_ref().set_modified(time)?)
+    }
+
+    /// Sets the modification time to now without changing the contents, like `touch` on an
+    /// existing file, for tests of cache invalidation
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_with_content(String::from("file_name.txt"), b"abc").unwrap();
+    /// t.touch().unwrap();
+    /// assert_eq!(t.len().unwrap(), 3);
+    /// ```
+    pub fn touch(&self) -> Result<()> {
+        self.set_mtime(std::time::SystemTime::now())
+    }
+
+    /// `true` when nothing was written to the file yet, the cursor isn't moved
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Current cursor position in bytes from the start of the file, the cursor isn't moved.
+    /// Same as [`Seek::stream_position`].
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"12345").unwrap();
+    /// assert_eq!(t.position().unwrap(), 5);
+    /// ```
+    pub fn position(&mut self) -> Result<u64> {
+        self.stream_position()
+    }
+
+    /// Checks that whatever the file was handed to read it to the end, failing with an error
+    /// saying how many bytes were left when the read cursor isn't at the end. With
+    /// [`TestTempFileBuilder::dual_cursor`] that's the cursor of the read handle.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Read;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"12345").unwrap();
+    /// let mut half = [0; 2];
+    /// t.read_exact(&mut half).unwrap();
+    /// assert!(t.assert_fully_read().is_err());
+    /// t.read_to_end(&mut Vec::new()).unwrap();
+    /// t.assert_fully_read().unwrap();
+    /// ```
+    pub fn assert_fully_read(&mut self) -> Result<()> {
+        let len = self.len()?;
+        let position = match self.reader {
+            Some(ref mut reader) => reader.stream_position()?,
+            None => self.position()?
+        };
+        if position >= len {
+            return Ok(());
+        }
+        Err(std::io::Error::other(format!(
+            "{} was read up to byte {}, {} of {} bytes were never read",
+            self.final_filename.display(),
+            position,
+            len - position,
+            len
+        )).into())
+    }
+
+    /// Current cursor position, same as [`Seek::stream_position`] and
+    /// [`position`](TestTempFile::position) without having to import `Seek`
+    pub fn stream_position(&mut self) -> Result<u64> {
+        Ok(Seek::stream_position(self)?)
+    }
+
+    /// Length of the file found by seeking to the end, with the cursor put back afterwards.
+    /// Counts buffered writes, unlike [`len`](TestTempFile::len).
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"12345").unwrap();
+    /// t.rewind().unwrap();
+    /// assert_eq!(t.stream_len().unwrap(), 5);
+    /// assert_eq!(t.stream_position().unwrap(), 0);
+    /// ```
+    pub fn stream_len(&mut self) -> Result<u64> {
+        let position = self.stream_position()?;
+        let len = self.seek(SeekFrom::End(0))?;
+        if position != len {
+            self.seek(SeekFrom::Start(position))?;
+        }
+        Ok(len)
+    }
+
+    /// Moves the cursor back to the start of the file, same as [`Seek::rewind`]
+    pub fn rewind(&mut self) -> Result<()> {
+        self.seek(SeekFrom::Start(0))?;
+        Ok(())
+    }
+
+    /// Empties the file and rewinds, so the next write starts a fresh file
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"first").unwrap();
+    /// t.truncate().unwrap();
+    /// t.write_all(b"second").unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "second");
+    /// ```
+    pub fn truncate(&mut self) -> Result<()> {
+        self.flush()?;
+        self.handle()?.get_ref().set_len(0)?;
+        self.rewind()
+    }
+
+    /// Resizes the file to `size` bytes, filling any new space with zeros, and rewinds
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.bin"));
+    /// t.preallocate(4096).unwrap();
+    /// assert_eq!(t.read_to_vec().unwrap(), vec![0; 4096]);
+    /// ```
+    pub fn preallocate(&mut self, size: u64) -> Result<()> {
+        self.flush()?;
+        self.handle()?.get_ref().set_len(size)?;
+        self.rewind()
+    }
+
+    /// Writes `len` copies of `byte` at the cursor, a recognizable pattern for spotting partial
+    /// overwrites, then rewinds. The bytes are written a chunk at a time, not one by one.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte` - The value every written byte has
+    /// * `len` - How many bytes are written
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.bin"));
+    /// t.fill(0xAB, 16).unwrap();
+    /// assert_eq!(t.read_to_vec().unwrap(), vec![0xAB; 16]);
+    /// ```
+    pub fn fill(&mut self, byte: u8, len: u64) -> Result<()> {
+        let buf = [byte; CHUNK_SIZE];
+        let mut remaining = len;
+        while remaining > 0 {
+            let n = remaining.min(CHUNK_SIZE as u64) as usize;
+            self.write_all(&buf[..n])?;
+            remaining -= n as u64;
+        }
+        self.flush()?;
+        self.rewind()
+    }
+
+    /// Writes `data` `iterations` times at the cursor, flushes, and returns how long that took,
+    /// for rough throughput checks in perf-oriented tests. Nothing is synced to disk, call
+    /// [`sync`](TestTempFile::sync) inside the measurement yourself if that should count.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Bytes written on every iteration
+    /// * `iterations` - How many times `data` is written
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.bin"));
+    /// let elapsed = t.timed_write(&[0; 4096], 256).unwrap();
+    /// println!("{:.1} MiB/s", 1.0 / elapsed.as_secs_f64());
+    /// ```
+    pub fn timed_write(&mut self, data: &[u8], iterations: usize) -> Result<std::time::Duration> {
+        let start = std::time::Instant::now();
+        for _ in 0..iterations {
+            self.write_all(data)?;
+        }
+        self.flush()?;
+        Ok(start.elapsed())
+    }
+
+    /// Shrinks or grows the file to exactly `len` bytes, growing fills the new tail with zeros.
+    /// The cursor stays where it was unless that's now past the end, then it's moved to the end.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The new length in bytes
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.bin"), b"0123456789").unwrap();
+    /// t.truncate_to(4).unwrap();
+    /// assert_eq!(t.read_to_vec().unwrap(), b"0123");
+    /// ```
+    pub fn truncate_to(&mut self, len: u64) -> Result<()> {
+        self.flush()?;
+        self.handle()?.get_ref().set_len(len)?;
+        if self.position()? > len {
+            self.seek(SeekFrom::Start(len))?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the whole contents with `data` and rewinds, ready to read the new contents
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The new contents
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"first payload").unwrap();
+    /// t.replace_contents(b"second").unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "second");
+    /// ```
+    pub fn replace_contents(&mut self, data: &[u8]) -> Result<()> {
+        self.truncate()?;
+        self.write_all(data)?;
+        self.flush()?;
+        self.rewind()
+    }
+
+    /// Replaces the contents with `data`, reads everything back and returns whether it came
+    /// back unchanged, the round trip this crate exists for in one call. The cursor is left
+    /// at the end of the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The bytes to write and expect back
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.bin"));
+    /// assert!(t.verify_roundtrip(b"\x00\xffsome bytes").unwrap());
+    /// ```
+    pub fn verify_roundtrip(&mut self, data: &[u8]) -> Result<bool> {
+        self.roundtrip_with(data, |t, data| t.write_all(data))
+    }
+
+    /// [`verify_roundtrip`](TestTempFile::verify_roundtrip) with the write step supplied by the caller
+    fn roundtrip_with<W>(&mut self, data: &[u8], write: W) -> Result<bool>
+        where W: FnOnce(&mut TestTempFile, &[u8]) -> std::io::Result<()>
+    {
+        self.truncate()?;
+        write(self, data)?;
+        self.flush()?;
+        Ok(self.read_to_vec()? == data)
+    }
+
+    /// Cuts the file off at the cursor, dropping any stale tail left by an earlier, longer
+    /// write after seeking back to rewrite the contents. The cursor doesn't move.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::{Seek, SeekFrom, Write};
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"longer first").unwrap();
+    /// t.seek(SeekFrom::Start(0)).unwrap();
+    /// t.write_all(b"short").unwrap();
+    /// t.compact().unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "short");
+    /// ```
+    pub fn compact(&mut self) -> Result<()> {
+        self.flush()?;
+        let position = self.position()?;
+        Ok(self.handle()?.get_ref().set_len(position)?)
+    }
+
+    /// Rewinds to the start of the file and reads all of it into a String,
+    /// leaving the cursor at the end of the file.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"some bytes").unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "some bytes");
+    /// ```
+    pub fn read_to_string(&mut self) -> Result<String> {
+        let mut content = String::new();
+        self.rewound_reader()?.read_to_string(&mut content)?;
+        Ok(content)
+    }
+
+    /// Rewinds to the start of the file and reads all of it into a Vec,
+    /// leaving the cursor at the end of the file.
+    pub fn read_to_vec(&mut self) -> Result<Vec<u8>> {
+        let mut content = Vec::new();
+        self.rewound_reader()?.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    /// Reads all of the file into a String like [`read_to_string`](TestTempFile::read_to_string),
+    /// but replaces invalid UTF-8 with `U+FFFD` instead of failing. The cursor is left where
+    /// it was.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"ok \xff").unwrap();
+    /// assert_eq!(t.read_to_string_lossy().unwrap(), "ok \u{FFFD}");
+    /// ```
+    pub fn read_to_string_lossy(&mut self) -> Result<String> {
+        let content = self.with_rewound(|t| t.read_to_vec())?;
+        Ok(String::from_utf8_lossy(&content).into_owned())
+    }
+
+    /// The whole contents, read from disk on the first call and cached for the following
+    /// ones, for asserting many things about the same output. The cache is dropped on the
+    /// next write, seek or read through the `TestTempFile`, writes through other handles
+    /// such as [`try_clone_handle`](TestTempFile::try_clone_handle) aren't noticed. The
+    /// cursor is left where it was.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"abc").unwrap();
+    /// assert_eq!(t.as_bytes().unwrap(), b"abc");
+    /// t.write_all(b"d").unwrap();
+    /// assert_eq!(t.as_bytes().unwrap(), b"dbc");
+    /// ```
+    pub fn as_bytes(&mut self) -> Result<&[u8]> {
+        if self.cached_contents.is_none() {
+            let content = self.with_rewound(|t| t.read_to_vec())?;
+            self.cached_contents = Some(content);
+        }
+        Ok(self.cached_contents.as_deref().unwrap_or_default())
+    }
+
+    /// Copies the whole contents into memory, the cursor is left where it was.
+    /// The returned cursor is independent of the file, later writes don't show up in it.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Read;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"abc").unwrap();
+    /// let mut snapshot = t.snapshot().unwrap();
+    /// let mut content = String::new();
+    /// snapshot.read_to_string(&mut content).unwrap();
+    /// assert_eq!(content, "abc");
+    /// ```
+    pub fn snapshot(&mut self) -> Result<std::io::Cursor<Vec<u8>>> {
+        let content = self.with_rewound(|t| {
+            let mut content = Vec::new();
+            t.handle_mut()?.get_mut().read_to_end(&mut content)?;
+            Ok(content)
+        })?;
+        Ok(std::io::Cursor::new(content))
+    }
+
+    /// The handle reads go through, moved to the start of the file. With
+    /// [`TestTempFileBuilder::dual_cursor`] that's the read handle and the write cursor stays put.
+    fn rewound_reader(&mut self) -> std::io::Result<&mut std::fs::File> {
+        self.flush()?;
+        match self.reader {
+            Some(ref mut reader) => {
+                reader.seek(SeekFrom::Start(0))?;
+                Ok(reader)
+            },
+            None => {
+                self.rewind()?;
+                Ok(self.handle_mut()?.get_mut())
+            }
+        }
+    }
+
+    /// Writes `data` at `offset` without moving the cursor, extending the file if needed.
+    /// Writing past the end leaves a gap of zeros, sparse where the filesystem supports it.
+    /// On unix this is a positional write, elsewhere the cursor is moved and put back.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Position in bytes from the start of the file
+    /// * `data` - The bytes to write
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"hello").unwrap();
+    /// t.write_at(1, b"ipp").unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "hippo");
+    /// ```
+    pub fn write_at(&mut self, offset: u64, data: &[u8]) -> Result<()> {
+        self.flush()?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileExt;
+            Ok(self.handle()?.get_ref().write_all_at(data, offset)?)
+        }
+        #[cfg(not(unix))]
+        {
+            let position = self.stream_position()?;
+            self.seek(SeekFrom::Start(offset))?;
+            let result = self.handle_mut()?.get_mut().write_all(data);
+            self.seek(SeekFrom::Start(position))?;
+            result
+        }
+    }
+
+    /// Rewinds and fills `buf` with the first bytes of the file, leaving the cursor right
+    /// after them. Fails with [`ErrorKind::UnexpectedEof`] if the file is shorter than `buf`.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - Filled with the header, its length is how many bytes are read
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.png"), b"\x89PNG\r\n").unwrap();
+    /// let mut magic = [0; 4];
+    /// t.read_header(&mut magic).unwrap();
+    /// assert_eq!(&magic, b"\x89PNG");
+    /// ```
+    pub fn read_header(&mut self, buf: &mut [u8]) -> Result<()> {
+        Ok(self.rewound_reader()?.read_exact(buf)?)
+    }
+
+    /// Reads into `buf` from `offset` without moving the cursor, returning how many bytes
+    /// were read, 0 at or past the end. Like [`Read::read`] it may read less than `buf` holds.
+    /// On unix this is a positional read, on Windows the cursor `seek_read` moves is put back.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Position in bytes from the start of the file
+    /// * `buf` - Where the bytes are read into
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"hippo").unwrap();
+    /// let mut buf = [0; 3];
+    /// assert_eq!(t.read_at(1, &mut buf).unwrap(), 3);
+    /// assert_eq!(&buf, b"ipp");
+    /// ```
+    pub fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        self.flush()?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileExt;
+            Ok(self.handle()?.get_ref().read_at(buf, offset)?)
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::FileExt;
+            let position = self.stream_position()?;
+            let result = self.handle()?.get_ref().seek_read(buf, offset);
+            self.seek(SeekFrom::Start(position))?;
+            result
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            let position = self.stream_position()?;
+            self.seek(SeekFrom::Start(offset))?;
+            let result = self.handle_mut()?.get_mut().read(buf);
+            self.seek(SeekFrom::Start(position))?;
+            result
+        }
+    }
+
+    /// The bytes in `start..end`, read without moving the cursor. Fails with
+    /// [`ErrorKind::InvalidInput`] when `start` is after `end` and with
+    /// [`ErrorKind::UnexpectedEof`] when the range goes past the end of the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Offset of the first byte
+    /// * `end` - Offset right after the last byte
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.bin"), b"header|record|").unwrap();
+    /// assert_eq!(t.read_range(7, 13).unwrap(), b"record");
+    /// ```
+    pub fn read_range(&mut self, start: u64, end: u64) -> Result<Vec<u8>> {
+        if start > end {
+            return Err(std::io::Error::new(ErrorKind::InvalidInput, format!("range {}..{} ends before it starts", start, end)).into());
+        }
+        let past_end = |t: &TestTempFile| {
+            let message = format!("range {}..{} goes past the end of {}", start, end, t.final_filename.display());
+            std::io::Error::new(ErrorKind::UnexpectedEof, message)
+        };
+        // checked before allocating so a huge end can't exhaust memory
+        if end > self.flushed_len()? {
+            return Err(past_end(self).into());
+        }
+        let len = usize::try_from(end - start).map_err(|_| past_end(self))?;
+        let mut range = vec![0; len];
+        let mut filled = 0;
+        while filled < range.len() {
+            match self.read_at(start + filled as u64, &mut range[filled..]) {
+                Ok(0) => return Err(past_end(self).into()),
+                Ok(n) => filled += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return Err(e)
+            }
+        }
+        Ok(range)
+    }
+
+    /// Writes `s` at the current cursor position, translating newlines according to
+    /// [`TestTempFileBuilder::newline_mode`]
+    pub fn write_str(&mut self, s: &str) -> Result<()> {
+        let s = self.builder.get_newline_mode().translate(s);
+        self.write_all(s.as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes all of `buf` at the current cursor position, retrying writes interrupted by a
+    /// signal instead of failing with [`ErrorKind::Interrupted`]. [`Write::write_all`] on a
+    /// `TestTempFile` does the same, this just spells it out at the call site.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The bytes to write
+    pub fn write_all_retrying(&mut self, buf: &[u8]) -> Result<()> {
+        Ok(write_all_retrying(self.handle_mut()?, buf)?)
+    }
+
+    /// Writes `s` followed by a newline at the end of the file
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.append_line("first").unwrap();
+    /// t.append_line("second").unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "first\nsecond\n");
+    /// ```
+    pub fn append_line(&mut self, s: &str) -> Result<()> {
+        self.seek(SeekFrom::End(0))?;
+        self.write_str(s)?;
+        self.write_str("\n")
+    }
+
+    /// Appends `text` with `\r\n` and lone `\r` line endings rewritten to `\n`, so text
+    /// assembled from mixed sources always ends up with LF line endings. The
+    /// [`newline_mode`](TestTempFileBuilder::newline_mode) isn't applied.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.append_normalized("windows\r\nold mac\r").unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "windows\nold mac\n");
+    /// ```
+    pub fn append_normalized(&mut self, text: &str) -> Result<()> {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        self.seek(SeekFrom::End(0))?;
+        self.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Iterates over the lines of the file starting from the beginning,
+    /// regardless of the current cursor position.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"a\nb\n").unwrap();
+    /// let lines: Vec<String> = t.lines().unwrap().map(|l| l.unwrap()).collect();
+    /// assert_eq!(lines, vec!["a", "b"]);
+    /// ```
+    pub fn lines(&mut self) -> Result<impl Iterator<Item = std::io::Result<String>> + '_> {
+        Ok(BufReader::new(self.rewound_reader()?).lines())
+    }
+
+    /// Iterates over the contents in chunks of `size` bytes starting from the beginning,
+    /// regardless of the current cursor position. The last chunk may be shorter.
+    /// Fails with [`ErrorKind::InvalidInput`] when `size` is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.bin"), b"abcde").unwrap();
+    /// let chunks: Vec<Vec<u8>> = t.chunks(2).unwrap().map(|c| c.unwrap()).collect();
+    /// assert_eq!(chunks, vec![b"ab".to_vec(), b"cd".to_vec(), b"e".to_vec()]);
+    /// ```
+    pub fn chunks(&mut self, size: usize) -> Result<impl Iterator<Item = std::io::Result<Vec<u8>>> + '_> {
+        if size == 0 {
+            return Err(std::io::Error::new(ErrorKind::InvalidInput, "chunk size must be at least 1").into());
+        }
+        let file = self.rewound_reader()?;
+        let mut done = false;
+        Ok(std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let mut chunk = vec![0; size];
+            match read_chunk(file, &mut chunk) {
+                Ok(0) => None,
+                Ok(n) => {
+                    chunk.truncate(n);
+                    done = n < size;
+                    Some(Ok(chunk))
+                },
+                Err(e) => {
+                    done = true;
+                    Some(Err(e))
+                }
+            }
+        }))
+    }
+
+    /// Compares the contents of both files chunk by chunk without reading either into
+    /// memory, the cursors of both files are left where they were.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut a = TestTempFile::new_with_content(String::from("a.txt"), b"same").unwrap();
+    /// let mut b = TestTempFile::new_with_content(String::from("b.txt"), b"same").unwrap();
+    /// assert!(a.content_equals(&mut b).unwrap());
+    /// ```
+    pub fn content_equals(&mut self, other: &mut TestTempFile) -> Result<bool> {
+        if self.flushed_len()? != other.flushed_len()? {
+            return Ok(false);
+        }
+        self.with_rewound(|t| other.with_rewound(|o| Ok(readers_equal(t.handle_mut()?.get_mut(), o.handle_mut()?.get_mut())?)))
+    }
+
+    /// Compares the contents chunk by chunk against whatever `other` yields, a decompressed
+    /// stream for example, without reading either side into memory. `false` on the first
+    /// difference, including one side ending early. The cursor is left where it was,
+    /// `other` is read up to the point of the difference.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The expected contents, read from its current position
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"golden").unwrap();
+    /// assert!(t.equals_reader(&mut std::io::Cursor::new(b"golden")).unwrap());
+    /// ```
+    pub fn equals_reader<R: Read>(&mut self, other: &mut R) -> Result<bool> {
+        self.with_rewound(|t| Ok(readers_equal(t.handle_mut()?.get_mut(), other)?))
+    }
+
+    /// `true` if the regular expression `pattern` matches anywhere in the contents, which
+    /// have to be UTF-8. Leaves the cursor at the end like [`read_to_string`](TestTempFile::read_to_string).
+    /// An invalid pattern fails with [`ErrorKind::InvalidInput`]. Requires the `regex` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - A regular expression in the syntax of the `regex` crate
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.log"), b"error: 42\n").unwrap();
+    /// assert!(t.contents_matches(r"error: \d+").unwrap());
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn contents_matches(&mut self, pattern: &str) -> Result<bool> {
+        let regex = regex::Regex::new(pattern).map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e))?;
+        Ok(regex.is_match(&self.read_to_string()?))
+    }
+
+    /// Maps the contents read-only into memory, after flushing and syncing so the map sees
+    /// everything written so far. The map stays valid after the `TestTempFile` is dropped
+    /// on unix, but the file must not be truncated while it's mapped, and on Windows it
+    /// can't be deleted until the map is dropped, so drop the map first. Requires the
+    /// `mmap` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.bin"), b"mapped").unwrap();
+    /// let map = t.mmap_readonly().unwrap();
+    /// assert_eq!(&map[..], b"mapped");
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub fn mmap_readonly(&mut self) -> Result<memmap2::Mmap> {
+        self.sync_data()?;
+        // the crate never truncates the file behind the caller's back, see the docs above
+        Ok(unsafe { memmap2::Mmap::map(self.handle()?.get_ref())? })
+    }
+
+    /// Appends the contents of the file at `src` to the end of this one and returns how many
+    /// bytes were added, leaving the cursor at the end. Fails with [`ErrorKind::NotFound`]
+    /// if `src` doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - The file whose contents are appended
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"header\n").unwrap();
+    /// t.append_from_path("Cargo.toml").unwrap();
+    /// assert!(t.read_to_string().unwrap().starts_with("header\n[package]"));
+    /// ```
+    pub fn append_from_path<P: AsRef<Path>>(&mut self, src: P) -> Result<u64> {
+        let mut source = std::fs::File::open(src)?;
+        self.seek(SeekFrom::End(0))?;
+        let appended = std::io::copy(&mut source, self)?;
+        self.flush()?;
+        Ok(appended)
+    }
+
+    /// Prints the contents to stderr under a `--- {label} ({path}) ---` header, for showing
+    /// what a file held when an assertion fails. Bytes that aren't UTF-8 are replaced.
+    /// The cursor is left where it was.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Printed in the header to tell several dumps apart
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"output").unwrap();
+    /// t.dump("parser output").unwrap();
+    /// ```
+    pub fn dump(&mut self, label: &str) -> Result<()> {
+        let content = self.with_rewound(|t| {
+            let mut content = Vec::new();
+            t.handle_mut()?.get_mut().read_to_end(&mut content)?;
+            Ok(content)
+        })?;
+        eprintln!("--- {} ({}) ---\n{}", label, self.final_filename.display(), String::from_utf8_lossy(&content));
+        Ok(())
+    }
+
+    /// Appends the whole contents of `other` to the end of this file and returns how many
+    /// bytes were added. The cursor of `other` is left where it was, this one ends up at the end.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The file whose contents are appended
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut a = TestTempFile::new_with_content(String::from("a.txt"), b"abc").unwrap();
+    /// let mut b = TestTempFile::new_with_content(String::from("b.txt"), b"def").unwrap();
+    /// assert_eq!(a.concat(&mut b).unwrap(), 3);
+    /// assert_eq!(a.read_to_string().unwrap(), "abcdef");
+    /// ```
+    pub fn concat(&mut self, other: &mut TestTempFile) -> Result<u64> {
+        self.seek(SeekFrom::End(0))?;
+        let appended = other.with_rewound(|o| Ok(std::io::copy(o.handle_mut()?.get_mut(), self)?))?;
+        self.flush()?;
+        Ok(appended)
+    }
+
+    /// Lowercase hex SHA-256 digest of the contents, computed in fixed-size chunks.
+    /// The cursor is left where it was. Requires the `hashing` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"abc").unwrap();
+    /// assert_eq!(
+    ///     t.sha256_hex().unwrap(),
+    ///     "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    /// );
+    /// ```
+    #[cfg(feature = "hashing")]
+    pub fn sha256_hex(&mut self) -> Result<String> {
+        use sha2::{Digest, Sha256};
+
+        let digest = self.with_rewound(|t| {
+            let mut hasher = Sha256::new();
+            let mut buf = [0; CHUNK_SIZE];
+            loop {
+                let n = read_chunk(t.handle_mut()?.get_mut(), &mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+            }
+            Ok(hasher.finalize())
+        })?;
+        Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    /// `true` if the SHA-256 digest of the contents is `expected_hex`, compared ignoring case,
+    /// for golden-file checks. The cursor is left where it was. Requires the `hashing` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_hex` - The expected digest as 64 hex digits
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new_with_content(String::from("file_name.txt"), b"abc").unwrap();
+    /// assert!(t.content_hash_matches("BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD").unwrap());
+    /// ```
+    #[cfg(feature = "hashing")]
+    pub fn content_hash_matches(&mut self, expected_hex: &str) -> Result<bool> {
+        Ok(self.sha256_hex()?.eq_ignore_ascii_case(expected_hex))
+    }
+
+    /// Runs `f` with the cursor at the start of the file and moves it back afterwards,
+    /// even if `f` fails
+    fn with_rewound<T, F>(&mut self, f: F) -> Result<T>
+        where F: FnOnce(&mut TestTempFile) -> Result<T>
+    {
+        let position = self.stream_position()?;
+        self.rewind()?;
+        let result = f(self);
+        self.seek(SeekFrom::Start(position))?;
+        result
+    }
+
+    /// Deletes the file now, along with any directories and links created for it like
+    /// [`delete`](TestTempFile::delete), and reports why if deleting the file fails.
+    /// After a successful call nothing is deleted on drop.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.try_delete().unwrap();
+    /// assert!(!t.path().exists());
+    /// ```
+    pub fn try_delete(&mut self) -> Result<()> {
+        remove_file(&self.final_filename)?;
+        self.remove_symlinks();
+        builder::remove_dirs(&self.created_dirs);
+        self.delete_on_drop = false;
+        Ok(())
+    }
+
+    /// Deletes the file now, along with any directories and links created for it, instead
+    /// of waiting for the drop. Calling it again, or dropping afterwards, does nothing.
+    /// Failures are reported on stderr like on drop, use [`try_delete`](TestTempFile::try_delete)
+    /// to get the error instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.delete();
+    /// assert!(!TestTempFile::exists(t.path()));
+    /// ```
+    pub fn delete(&mut self) {
+        self.delete_file();
+        self.delete_on_drop = false;
+    }
+
+    /// Deletes the file and replaces it with a new, empty one under a fresh random name in
+    /// the same directory, so one `TestTempFile` can be reused across loop iterations.
+    /// The old file is gone before the new one is created.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// let old = t.path().to_path_buf();
+    /// t.write_all(b"first run").unwrap();
+    /// t.regenerate().unwrap();
+    /// assert!(!old.exists());
+    /// assert!(t.is_empty().unwrap());
+    /// ```
+    pub fn regenerate(&mut self) -> Result<()> {
+        match remove_file(&self.final_filename) {
+            Ok(()) => (),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => return Err(e.into())
+        }
+        self.remove_symlinks();
+        let mut fresh = self.builder.clone().without_seed().build()?;
+        std::mem::swap(&mut self.random_number, &mut fresh.random_number);
+        std::mem::swap(&mut self.final_filename, &mut fresh.final_filename);
+        std::mem::swap(&mut self.file, &mut fresh.file);
+        std::mem::swap(&mut self.reader, &mut fresh.reader);
+        self.cached_contents = None;
+        self.delete_on_drop = true;
+        // fresh now holds the old, already deleted file
+        fresh.delete_on_drop = false;
+        Ok(())
+    }
+
+    /// `true` when something exists at `path`, for asserting a file was cleaned up
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// let path = t.path().to_path_buf();
+    /// assert!(TestTempFile::exists(&path));
+    /// drop(t);
+    /// assert!(!TestTempFile::exists(&path));
+    /// ```
+    pub fn exists<P: AsRef<Path>>(path: P) -> bool {
+        std::fs::symlink_metadata(path).is_ok()
+    }
+
+    /// Creates a new, empty temp file next to this one with the same name but extension
+    /// `ext`, so `data_{random}.dat` gets `data_{random}.idx`, for tools writing files in
+    /// pairs. Both are deleted on their own drop. An empty `ext` gives the name without an
+    /// extension. Fails with [`ErrorKind::AlreadyExists`] if the sibling exists and with
+    /// [`ErrorKind::InvalidInput`] if `ext` isn't allowed in a file name.
+    ///
+    /// # Arguments
+    ///
+    /// * `ext` - Extension of the sibling, without the dot
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let data = TestTempFile::new(String::from("table.dat"));
+    /// let index = data.sibling_with_extension("idx").unwrap();
+    /// assert_eq!(index.path(), data.path().with_extension("idx"));
+    /// ```
+    pub fn sibling_with_extension(&self, ext: &str) -> Result<TestTempFile> {
+        name::validate(ext)?;
+        if ext.contains(std::path::is_separator) {
+            return Err(name::invalid_name(ext, "an extension can't contain a path separator").into());
+        }
+        let path = self.final_filename.with_extension(ext);
+        Ok(self.builder.clone().without_seed().create_at(path, self.random_number)?)
+    }
+
+    /// Creates a new, independently managed temp file with a fresh random name and a copy
+    /// of the current contents. The cursor of both files is left where the original's was.
+    /// With [`TestTempFileBuilder::buffered`] only what was flushed is copied.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"some bytes").unwrap();
+    /// let mut copy = t.try_clone().unwrap();
+    /// assert_ne!(t.path(), copy.path());
+    /// assert_eq!(copy.read_to_string().unwrap(), "some bytes");
+    /// ```
+    pub fn try_clone(&self) -> Result<TestTempFile> {
+        let mut clone = self.builder.clone().without_seed().build()?;
+        let mut file = self.handle()?.get_ref();
+        let position = file.stream_position()?;
+        let copied = copy::copy_file(file, clone.handle()?.get_ref());
+        file.seek(SeekFrom::Start(position))?;
+        copied?;
+        clone.seek(SeekFrom::Start(position))?;
+        Ok(clone)
+    }
+
+    /// Copies the current contents to a newly created file at `dest`, replacing it if it
+    /// exists, and returns how many bytes were copied. Unlike [`persist`](TestTempFile::persist)
+    /// the temp file stays usable, the cursor is left where it was and it's still deleted on drop.
+    ///
+    /// # Arguments
+    ///
+    /// * `dest` - Where the copy is written, its directory must already exist
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"snapshot").unwrap();
+    /// let dest = std::env::temp_dir().join("snapshot.txt");
+    /// assert_eq!(t.copy_to(&dest).unwrap(), 8);
+    /// # std::fs::remove_file(dest).unwrap();
+    /// ```
+    pub fn copy_to<P: AsRef<Path>>(&mut self, dest: P) -> Result<u64> {
+        let dest = std::fs::File::create(dest)?;
+        self.flush()?;
+        self.with_rewound(|t| Ok(copy::copy_file(t.handle()?.get_ref(), &dest)?))
+    }
+
+    /// Copies everything into `writer`, then empties the file and rewinds, returning how
+    /// many bytes were drained. Models a buffer that's flushed downstream batch by batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Where the contents go
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"batch").unwrap();
+    /// let mut downstream = Vec::new();
+    /// assert_eq!(t.drain_to(&mut downstream).unwrap(), 5);
+    /// assert!(t.is_empty().unwrap());
+    /// ```
+    pub fn drain_to<W: Write>(&mut self, writer: &mut W) -> Result<u64> {
+        let drained = std::io::copy(self.rewound_reader()?, writer)?;
+        self.truncate()?;
+        Ok(drained)
+    }
+
+    /// Another handle to the same open file, see [`std::fs::File::try_clone`]. Both share
+    /// the cursor, and the file is still deleted when this `TestTempFile` is dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.try_clone_handle().unwrap().write_all(b"shared").unwrap();
+    /// assert_eq!(t.read_to_string().unwrap(), "shared");
+    /// ```
+    pub fn try_clone_handle(&self) -> Result<std::fs::File> {
+        Ok(self.handle()?.get_ref().try_clone()?)
+    }
+
+    /// Splits the file into a writer and an independent reader over the same file, for
+    /// producer/consumer tests. The reader gets a fresh read-only handle starting at the
+    /// beginning, the writer keeps the cursor of this one. The file is deleted once both
+    /// halves are dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.log"));
+    /// let (mut reader, mut writer) = t.into_reader_writer().unwrap();
+    /// writer.write_all(b"event").unwrap();
+    /// writer.flush().unwrap();
+    /// let mut content = String::new();
+    /// reader.read_to_string(&mut content).unwrap();
+    /// assert_eq!(content, "event");
+    /// ```
+    pub fn into_reader_writer(self) -> Result<(TempReader, TempWriter)> {
+        Ok(split::split(self)?)
+    }
+
+    /// The underlying [`File`](std::fs::File), for APIs that need one, such as memory mapping.
+    /// Writes still sitting in a [`buffered`](TestTempFileBuilder::buffered) buffer aren't
+    /// in it yet, flush first.
+    ///
+    /// # Panics
+    ///
+    /// Panics after [`close`](TestTempFile::close).
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new_with_content(String::from("file_name.txt"), b"abc").unwrap();
+    /// assert_eq!(t.as_file().metadata().unwrap().len(), 3);
+    /// ```
+    pub fn as_file(&self) -> &std::fs::File {
+        self.handle().expect("file was closed").get_ref()
+    }
+
+    /// Mutable access to the underlying [`File`](std::fs::File). Pending buffered writes are
+    /// flushed first so writing through it doesn't reorder them.
+    ///
+    /// # Panics
+    ///
+    /// Panics after [`close`](TestTempFile::close) or if flushing fails.
+    pub fn as_file_mut(&mut self) -> &mut std::fs::File {
+        let handle = self.handle_mut().expect("file was closed");
+        handle.flush().expect("failed to flush buffered writes");
+        handle.get_mut()
+    }
+
+    /// Consumes the `TestTempFile` and hands over the underlying [`File`](std::fs::File).
+    /// The file isn't deleted on drop anymore, removing it is up to the caller then, like
+    /// after [`keep`](TestTempFile::keep).
+    ///
+    /// # Panics
+    ///
+    /// Same as [`as_file_mut`](TestTempFile::as_file_mut).
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// let path = t.path().to_path_buf();
+    /// let mut file = t.into_inner();
+    /// file.write_all(b"mine now").unwrap();
+    /// assert_eq!(std::fs::read(&path).unwrap(), b"mine now");
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    pub fn into_inner(mut self) -> std::fs::File {
+        let handle = self.file.take().expect("file was closed");
+        self.delete_on_drop = false;
+        self.commit_target = None;
+        handle.into_inner().map_err(|e| e.into_error()).expect("failed to flush buffered writes")
+    }
+
+    /// A [`LineWriter`](std::io::LineWriter) over the file for the text parts of mixed
+    /// binary and text output, flushing on every newline. Whatever is left after the last
+    /// newline is flushed when it's dropped, which has to happen before the file is used
+    /// again, so writes through the two views never overlap.
+    ///
+    /// # Panics
+    ///
+    /// Same as [`as_file_mut`](TestTempFile::as_file_mut).
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.dat"));
+    /// t.binary_writer().write_all(&[0x7f, b'D', b'A', b'T']).unwrap();
+    /// writeln!(t.text_writer(), "rows: {}", 3).unwrap();
+    /// assert_eq!(t.read_to_vec().unwrap(), b"\x7fDATrows: 3\n");
+    /// ```
+    pub fn text_writer(&mut self) -> std::io::LineWriter<&mut std::fs::File> {
+        std::io::LineWriter::new(self.as_file_mut())
+    }
+
+    /// Raw access to the file for the binary parts of mixed output, see
+    /// [`text_writer`](TestTempFile::text_writer). Same as [`as_file_mut`](TestTempFile::as_file_mut).
+    pub fn binary_writer(&mut self) -> &mut std::fs::File {
+        self.as_file_mut()
+    }
+
+    /// A writer sending everything to the file and to `other`, for capturing output while
+    /// still forwarding it somewhere like stdout. Bytes go to the file first and exactly the
+    /// ones it accepted are then written to `other`, so both always hold the same data.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The second sink
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.log"));
+    /// let mut mirror = Vec::new();
+    /// writeln!(t.tee(&mut mirror), "started").unwrap();
+    /// assert_eq!(mirror, b"started\n");
+    /// assert_eq!(t.read_to_string().unwrap(), "started\n");
+    /// ```
+    pub fn tee<'a, W: Write + 'a>(&'a mut self, other: W) -> impl Write + 'a {
+        Tee { file: self, other }
+    }
+
+    /// Takes an exclusive advisory lock on the file, blocking until it's available.
+    /// Uses `flock` on unix and `LockFileEx` on Windows, so other handles opened on the path
+    /// fail to lock it until [`unlock`](TestTempFile::unlock) is called or the file is dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.lock"));
+    /// t.lock_exclusive().unwrap();
+    /// t.unlock().unwrap();
+    /// ```
+    #[cfg(feature = "locking")]
+    pub fn lock_exclusive(&self) -> Result<()> {
+        lock::lock_exclusive(self.handle()?.get_ref(), true)?;
+        Ok(())
+    }
+
+    /// Releases a lock taken with [`lock_exclusive`](TestTempFile::lock_exclusive)
+    #[cfg(feature = "locking")]
+    pub fn unlock(&self) -> Result<()> {
+        Ok(lock::unlock(self.handle()?.get_ref())?)
+    }
+
+    /// Moves the file to `dest` instead of deleting it and returns the new path.
+    /// Falls back to copying when `dest` is on another filesystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `dest` - Where the file ends up, its directory must already exist
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"golden").unwrap();
+    /// let path = t.persist(std::env::temp_dir().join("golden.txt")).unwrap();
+    /// assert_eq!(std::fs::read(&path).unwrap(), b"golden");
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    pub fn persist<P: AsRef<Path>>(mut self, dest: P) -> Result<PathBuf> {
+        let dest = dest.as_ref();
+        self.sync()?;
+        move_file(&self.final_filename, dest)?;
+        self.delete_on_drop = false;
+        self.remove_symlinks();
+        builder::remove_dirs(&self.created_dirs);
+        Ok(dest.to_path_buf())
+    }
+
+    /// Atomically renames the file to `final_name` in the same directory and returns the new
+    /// path, the counterpart of [`TestTempFileBuilder::tmp_suffix`]. After that the file
+    /// isn't deleted on drop, without a commit the `.tmp` file is deleted as usual.
+    ///
+    /// # Arguments
+    ///
+    /// * `final_name` - The name the file ends up with, relative to the directory it's in
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::builder().dir(std::env::temp_dir()).tmp_suffix(true).build().unwrap();
+    /// t.write_all(b"complete").unwrap();
+    /// let path = t.commit("output.txt").unwrap();
+    /// assert_eq!(std::fs::read(&path).unwrap(), b"complete");
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    pub fn commit<P: AsRef<Path>>(mut self, final_name: P) -> Result<PathBuf> {
+        let dest = self.final_filename.with_file_name(final_name.as_ref());
+        self.sync()?;
+        std::fs::rename(&self.final_filename, &dest)?;
+        self.delete_on_drop = false;
+        self.remove_symlinks();
+        self.final_filename = dest.clone();
+        Ok(dest)
+    }
+
+    /// Moves the file to `target` when it's dropped instead of deleting it, replacing whatever
+    /// is there. The rename is atomic on the same filesystem, across filesystems the file is
+    /// copied and removed. Call it once the test got far enough for the result to count, a
+    /// panic before that leaves `target` untouched. If the move fails the file is deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Where the file ends up, its directory must already exist
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let target = std::env::temp_dir().join("swapped_in.txt");
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"new contents").unwrap();
+    /// t.commit_on_drop(target.clone());
+    /// drop(t);
+    /// assert_eq!(std::fs::read(&target).unwrap(), b"new contents");
+    /// # std::fs::remove_file(target).unwrap();
+    /// ```
+    pub fn commit_on_drop(&mut self, target: PathBuf) {
+        self.commit_target = Some(target);
+    }
+
+    /// Changes the permissions of the file, see [`std::fs::File::set_permissions`].
+    /// The file is still deleted on drop after being made read-only.
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// let mut permissions = std::fs::metadata(t.path()).unwrap().permissions();
+    /// permissions.set_readonly(true);
+    /// t.set_permissions(permissions).unwrap();
+    /// ```
+    pub fn set_permissions(&self, permissions: std::fs::Permissions) -> Result<()> {
+        Ok(self.handle()?.get_ref().set_permissions(permissions)?)
+    }
+
+    /// The effective permission bits of the file, after the umask was applied to the
+    /// [`mode`](TestTempFileBuilder::mode) it was created with
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFileBuilder;
+    /// let t = TestTempFileBuilder::new().mode(0o600).build().unwrap();
+    /// assert_eq!(t.mode_bits().unwrap(), 0o600);
+    /// ```
+    #[cfg(unix)]
+    pub fn mode_bits(&self) -> Result<u32> {
+        use std::os::unix::fs::PermissionsExt;
+        Ok(self.handle()?.get_ref().metadata()?.permissions().mode() & 0o777)
+    }
+
+    /// Changes the owner and group of the file, `None` leaves that one as it is, see
+    /// [`fchown`](std::os::unix::fs::fchown). Changing the owner usually needs root.
+    ///
+    /// # Arguments
+    ///
+    /// * `uid` - The new owner
+    /// * `gid` - The new group
+    ///
+    /// # Examples
+    /// ```
+    /// use std::os::unix::fs::MetadataExt;
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// let gid = t.path().metadata().unwrap().gid();
+    /// t.chown(None, Some(gid)).unwrap();
+    /// ```
+    #[cfg(unix)]
+    pub fn chown(&self, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        Ok(std::os::unix::fs::fchown(self.handle()?.get_ref(), uid, gid)?)
+    }
+
+    /// Creates a symbolic link at `link_path` pointing to the file. The link is removed
+    /// together with the file, on drop or by [`persist`](TestTempFile::persist).
+    /// Creating symlinks may need extra privileges on Windows, the error from the OS
+    /// is returned in that case.
+    ///
+    /// # Arguments
+    ///
+    /// * `link_path` - Where the link is created, nothing may exist there yet
+    ///
+    /// # Examples
+    /// ```
+    /// use test_temp_file::TestTempFile;
+    /// let t = TestTempFile::new(String::from("file_name.txt"));
+    /// let link = std::env::temp_dir().join(format!("link_to_{}", t.path().display()));
+    /// # #[cfg(unix)]
+    /// t.create_symlink(&link).unwrap();
+    /// ```
+    pub fn create_symlink<P: AsRef<Path>>(&self, link_path: P) -> Result<()> {
+        let link_path = link_path.as_ref();
+        // a relative target would be resolved against the link's directory
+        let target = std::env::current_dir()?.join(&self.final_filename);
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&target, link_path)?;
+        #[cfg(windows)]
+        std::os::windows::fs::symlink_file(&target, link_path)?;
+        #[cfg(not(any(unix, windows)))]
+        return Err(std::io::Error::new(ErrorKind::Unsupported, "symlinks aren't supported on this platform"));
+        self.symlinks.lock().unwrap_or_else(|e| e.into_inner()).push(link_path.to_path_buf());
+        Ok(())
+    }
+
+    fn remove_symlinks(&mut self) {
+        let symlinks = self.symlinks.get_mut().unwrap_or_else(|e| e.into_inner());
+        for link in symlinks.drain(..) {
+            match std::fs::remove_file(&link) {
+                Ok(()) => (),
+                Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+                Err(e) => eprintln!("test-temp-file: failed to delete {}: {}", link.display(), e)
+            }
+        }
+    }
+
+    /// Flushes and syncs the file, then closes its handle while the path stays managed, it's
+    /// still deleted on drop. Reads, writes and seeks fail afterwards, for example so another
+    /// process can rename or delete the file on Windows while the test keeps running.
+    /// Closing again does nothing.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    /// use test_temp_file::TestTempFile;
+    /// let mut t = TestTempFile::new(String::from("file_name.txt"));
+    /// t.write_all(b"some bytes").unwrap();
+    /// t.close().unwrap();
+    /// assert!(t.write_all(b"more").is_err());
+    /// assert_eq!(std::fs::read(t.path()).unwrap(), b"some bytes");
+    /// ```
+    pub fn close(&mut self) -> Result<()> {
+        self.sync()?;
+        self.reader = None;
+        self.file = None;
+        self.cached_contents = None;
+        Ok(())
+    }
+
+    fn handle(&self) -> std::io::Result<&std::io::BufWriter<std::fs::File>> {
+        match self.file {
+            Some(ref file) => Ok(file),
+            None => Err(self.closed_error())
+        }
+    }
+
+    fn handle_mut(&mut self) -> std::io::Result<&mut std::io::BufWriter<std::fs::File>> {
+        self.cached_contents = None;
+        match self.file {
+            Some(ref mut file) => Ok(file),
+            None => Err(self.closed_error())
+        }
+    }
+
+    fn closed_error(&self) -> std::io::Error {
+        std::io::Error::other(format!("{} was closed", self.final_filename.display()))
+    }
+
+    fn delete_file(&mut self) {
+        match remove_file(&self.final_filename) {
+            Ok(()) => (),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => eprintln!("test-temp-file: failed to delete {}: {}", self.final_filename.display(), e)
+        }
+        self.remove_symlinks();
+        builder::remove_dirs(&self.created_dirs);
+    }
+}
+
+/// Environment variable that turns off deleting files on drop
+const KEEP_VAR: &str = "TEST_TEMP_FILE_KEEP";
+
+/// `true` when [`KEEP_VAR`] is set to something other than `0` or nothing
+fn keep_requested() -> bool {
+    match std::env::var_os(KEEP_VAR) {
+        Some(value) => !value.is_empty() && value != "0",
+        None => false
+    }
+}
+
+/// Deletes files left behind in `dir` by earlier runs that never got to drop them, for
+/// example after a crash or `SIGKILL`. Only files named the way this crate names them by
+/// default, `{name}_ttf` and 20 digits with an optional extension, that were last modified
+/// at least `max_age` ago are removed. Returns how many were deleted.
+///
+/// # Arguments
+///
+/// * `dir` - Directory to scan, subdirectories aren't entered
+/// * `max_age` - How long a file has to be untouched before it counts as orphaned
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use test_temp_file::{cleanup_orphans, TestTempDir};
+/// let dir = TestTempDir::new(String::from("previous_run"));
+/// let removed = cleanup_orphans(dir.path(), Duration::from_secs(24 * 60 * 60)).unwrap();
+/// assert_eq!(removed, 0);
+/// ```
+pub fn cleanup_orphans<P: AsRef<Path>>(dir: P, max_age: std::time::Duration) -> Result<usize> {
+    let now = std::time::SystemTime::now();
+    let mut removed = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if !name::is_generated(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        let age = now.duration_since(metadata.modified()?).unwrap_or_default();
+        if metadata.is_file() && age >= max_age {
+            match remove_file(&entry.path()) {
+                Ok(()) => removed += 1,
+                Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+                Err(e) => return Err(e.into())
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// Size of the chunks used when streaming through file contents
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Reads from `reader` until `buf` is full or the end is reached, returning how much was read
+fn read_chunk<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        }
+    }
+    Ok(filled)
+}
+
+/// Writes all of `buf` to `writer`, retrying on [`ErrorKind::Interrupted`] until some
+/// progress is made or a different error comes back
+fn write_all_retrying<W: Write>(writer: &mut W, mut buf: &[u8]) -> std::io::Result<()> {
+    while !buf.is_empty() {
+        match writer.write(buf) {
+            Ok(0) => return Err(std::io::Error::new(ErrorKind::WriteZero, "failed to write the whole buffer")),
+            Ok(n) => buf = &buf[n..],
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        }
+    }
+    Ok(())
+}
+
+/// Compares two readers chunk by chunk until the first difference
+fn readers_equal<A: Read, B: Read>(a: &mut A, b: &mut B) -> std::io::Result<bool> {
+    let mut a_buf = [0; CHUNK_SIZE];
+    let mut b_buf = [0; CHUNK_SIZE];
+    loop {
+        let a_len = read_chunk(a, &mut a_buf)?;
+        let b_len = read_chunk(b, &mut b_buf)?;
+        if a_buf[..a_len] != b_buf[..b_len] {
+            return Ok(false);
+        }
+        if a_len == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Directory the `new_in_temp_dir` constructors create files and directories in,
+/// resolved from the environment on every call
+pub(crate) fn temp_dir() -> PathBuf {
+    std::env::temp_dir()
+}
+
+/// Removes the file at `path`, clearing the read-only attribute first on Windows where
+/// it would prevent deletion
+fn remove_file(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        #[cfg(windows)]
+        Err(ref e) if e.kind() == ErrorKind::PermissionDenied => {
+            let mut permissions = std::fs::metadata(path)?.permissions();
+            permissions.set_readonly(false);
+            std::fs::set_permissions(path, permissions)?;
+            std::fs::remove_file(path)
+        },
+        result => result
+    }
+}
+
+/// Renames `from` to `to`, copying and removing `from` when they're on different filesystems
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    move_file_with(from, to, |from, to| std::fs::rename(from, to))
+}
+
+/// OS error `rename` fails with when the destination is on another filesystem, `EXDEV` on
+/// unix and `ERROR_NOT_SAME_DEVICE` on Windows
+#[cfg(unix)]
+const CROSSES_DEVICES: i32 = 18;
+#[cfg(windows)]
+const CROSSES_DEVICES: i32 = 17;
+
+fn crosses_devices(e: &std::io::Error) -> bool {
+    #[cfg(any(unix, windows))]
+    {
+        e.raw_os_error() == Some(CROSSES_DEVICES)
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = e;
+        false
+    }
+}
+
+/// [`move_file`] with the rename step passed in, so tests can make it cross devices
+fn move_file_with<R>(from: &Path, to: &Path, rename: R) -> std::io::Result<()>
+    where R: FnOnce(&Path, &Path) -> std::io::Result<()>
+{
+    match rename(from, to) {
+        Err(ref e) if crosses_devices(e) => {
+            if let Err(e) = std::fs::copy(from, to) {
+                // don't leave a partial copy behind, the original is still in place
+                let _ = std::fs::remove_file(to);
+                return Err(e);
+            }
+            std::fs::remove_file(from)
+        },
+        result => result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    use std::str;
+
+    static FILE_NAME: &str = "test_file.txt";
+
+    #[test]
+    fn test_constructor() {
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        assert_eq!(FILE_NAME, t.filename);
+    }
+
+    #[test]
+    fn test_write() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        match t.write_all(b"some bytes") {
+            Ok(_) => (),
+            Err(e) => panic!("{}", e)
+        }
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        let mut buffer = [0; 10];
+        let msg = b"some bytes";
+        match t.write_all(msg) {
+            Ok(_) => (),
+            Err(e) => panic!("{}", e)
+        }
+
+        // Need to rewind pointer inside file, since after the write we're pointing to the end
+
+        t.seek(SeekFrom::Start(0)).unwrap();
+
+        match t.read(&mut buffer[..]) {
+            Ok(n) => {
+                assert_eq!(msg,
+                           &buffer[..n],
+                           "Left:{:#?}\nRight:{:#?}\n{:#?}",
+                           msg, &buffer[..n], t)
+            },
+            Err(e) => panic!("Error:{}\n{:#?}", e, t)
+        }
+    }
+
+    #[test]
+    fn test_try_new_error() {
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        let result = TestTempFile::try_new(format!("{}/test_file.txt", t.path().display()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_new_nested() {
+        let t = TestTempFile::new(String::from("test_subdir/fixture.txt"));
+        let random = t.random_number;
+        assert_eq!(t.path(), Path::new(&format!("test_subdir/fixture_{}.txt", name::tagged(random))));
+        assert!(t.path().exists());
+        drop(t);
+        assert!(!Path::new("test_subdir").exists());
+    }
+
+    #[test]
+    fn test_name_keeps_extension() {
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        let name = t.final_filename.to_str().unwrap();
+        let random = format!("_{}.", name::tagged(t.random_number));
+        assert_eq!(name, format!("test_file{}txt", random));
+    }
+
+    #[test]
+    fn test_name_multiple_dots() {
+        let t = TestTempFile::new(String::from("archive.tar.gz"));
+        let name = t.final_filename.to_str().unwrap();
+        assert_eq!(name, format!("archive.tar_{}.gz", name::tagged(t.random_number)));
+    }
+
+    #[test]
+    fn test_name_without_extension() {
+        let t = TestTempFile::new(String::from("Makefile"));
+        let name = t.final_filename.to_str().unwrap();
+        assert_eq!(name, format!("Makefile_{}", name::tagged(t.random_number)));
+        assert_eq!(t.final_filename.extension(), None);
+    }
+
+    #[test]
+    fn test_path() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"some bytes").unwrap();
+        let mut content = String::new();
+        std::fs::File::open(t.path()).unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "some bytes");
+        assert_eq!(t.path(), t.final_filename.as_path());
+        assert!(std::fs::metadata(&t).unwrap().is_file());
+    }
+
+    #[test]
+    fn test_keep() {
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        let path = t.keep();
+        assert!(path.exists());
+        std::fs::remove_file(path).unwrap();
+
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        let path = t.path().to_path_buf();
+        drop(t);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_unique_names_across_threads() {
+        let handles: Vec<_> = (0..16).map(|_| {
+            std::thread::spawn(|| {
+                (0..16).map(|_| TestTempFile::new_in_temp_dir(String::from(FILE_NAME)).unwrap())
+                    .collect::<Vec<_>>()
+            })
+        }).collect();
+        let files: Vec<TestTempFile> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        let paths: std::collections::HashSet<&Path> = files.iter().map(|t| t.path()).collect();
+        assert_eq!(paths.len(), files.len());
+    }
+
+    #[test]
+    fn test_read_to_string() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"some bytes").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "some bytes");
+        assert_eq!(t.stream_position().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_read_to_vec() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(&[0, 159, 146, 150]).unwrap();
+        assert_eq!(t.read_to_vec().unwrap(), vec![0, 159, 146, 150]);
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"first\nsecond\nthird\n").unwrap();
+        let lines = t.lines().unwrap().collect::<std::io::Result<Vec<String>>>().unwrap();
+        assert_eq!(lines, vec!["first", "second", "third"]);
+    }
+
+    #[test]
+    fn test_new_with_content() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"hello").unwrap();
+        let mut content = Vec::new();
+        t.read_to_end(&mut content).unwrap();
+        assert_eq!(content, b"hello");
+    }
+
+    #[test]
+    fn test_try_delete() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.try_delete().unwrap();
+        assert!(!t.path().exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_try_delete_removes_dirs_and_links() {
+        let dir = TestTempDir::new(String::from("try_delete_links"));
+        let link = dir.child("link.txt");
+        let mut t = TestTempFile::new(String::from("nested_try_delete/test_file.txt"));
+        t.create_symlink(&link).unwrap();
+        t.try_delete().unwrap();
+        assert!(!TestTempFile::exists(t.path()));
+        assert!(!TestTempFile::exists("nested_try_delete"));
+        assert!(std::fs::symlink_metadata(&link).is_err());
+    }
+
+    #[test]
+    fn test_try_delete_reports_error() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        std::fs::remove_file(t.path()).unwrap();
+        let e = t.try_delete().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_try_clone() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"some bytes").unwrap();
+        t.seek(SeekFrom::Start(5)).unwrap();
+
+        let mut clone = t.try_clone().unwrap();
+        assert_ne!(t.path(), clone.path());
+        assert_eq!(t.stream_position().unwrap(), 5);
+        assert_eq!(clone.stream_position().unwrap(), 5);
+
+        clone.write_all(b"BYTES").unwrap();
+        assert_eq!(clone.read_to_string().unwrap(), "some BYTES");
+        assert_eq!(t.read_to_string().unwrap(), "some bytes");
+
+        let clone_path = clone.path().to_path_buf();
+        drop(clone);
+        assert!(!clone_path.exists());
+        assert!(t.path().exists());
+    }
+
+    #[test]
+    fn test_persist() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"some bytes").unwrap();
+        let temp_path = t.path().to_path_buf();
+        let dest = std::env::temp_dir().join(format!("persisted_{}.txt", t.random_number));
+        let path = t.persist(&dest).unwrap();
+        assert_eq!(path, dest);
+        assert!(!temp_path.exists());
+        assert_eq!(std::fs::read(&dest).unwrap(), b"some bytes");
+        std::fs::remove_file(dest).unwrap();
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn test_move_file_crosses_devices() {
+        #[cfg(all(feature = "fast-copy", unix))]
+        assert_eq!(CROSSES_DEVICES, libc::EXDEV);
+        let dir = TestTempDir::new(String::from("crosses_devices"));
+        let from = dir.child("from.bin");
+        let to = dir.child("to.bin");
+        let content: Vec<u8> = (0..100_000).map(|i| (i % 253) as u8).collect();
+        std::fs::write(&from, &content).unwrap();
+        let exdev = |_: &Path, _: &Path| Err(std::io::Error::from_raw_os_error(CROSSES_DEVICES));
+        move_file_with(&from, &to, exdev).unwrap();
+        assert!(!from.exists());
+        assert_eq!(std::fs::read(&to).unwrap(), content);
+
+        let e = move_file_with(&dir.child("missing.bin"), &dir.child("partial.bin"), exdev).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+        assert!(!dir.child("partial.bin").exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_persist_across_filesystems() {
+        use std::os::unix::fs::MetadataExt;
+
+        let other = Path::new("/dev/shm");
+        let t = TestTempFile::new_with_content(String::from(FILE_NAME), b"moved across mounts").unwrap();
+        match other.metadata() {
+            Ok(metadata) if metadata.is_dir() && metadata.dev() != t.metadata().unwrap().dev() => (),
+            _ => {
+                eprintln!("skipping test_persist_across_filesystems, /dev/shm isn't a separate filesystem");
+                return;
+            }
+        }
+        let temp_path = t.path().to_path_buf();
+        let dest = other.join(format!("persisted_{}.txt", t.random_number));
+        let path = t.persist(&dest).unwrap();
+        assert!(!temp_path.exists());
+        assert_eq!(std::fs::read(&path).unwrap(), b"moved across mounts");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_persist_missing_dir() {
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        let dest = std::env::temp_dir().join("no_such_dir").join(FILE_NAME);
+        let e = t.persist(dest).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_io_copy_between_files() {
+        let mut src = TestTempFile::new(String::from(FILE_NAME));
+        let mut dst = TestTempFile::new(String::from(FILE_NAME));
+        let content: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        src.write_all(&content).unwrap();
+        src.seek(SeekFrom::Start(0)).unwrap();
+
+        let copied = std::io::copy(&mut src, &mut dst).unwrap();
+        assert_eq!(copied, content.len() as u64);
+        assert_eq!(dst.read_to_vec().unwrap(), content);
+    }
+
+    #[test]
+    fn test_generic_bounds() {
+        fn write_and_rewind<W: Write + Seek>(w: &mut W) {
+            w.write_all(b"generic").unwrap();
+            w.seek(SeekFrom::Start(0)).unwrap();
+        }
+
+        fn read_all<R: Read>(r: &mut R) -> Vec<u8> {
+            let mut content = Vec::new();
+            r.read_to_end(&mut content).unwrap();
+            content
+        }
+
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        write_and_rewind(&mut t);
+        assert_eq!(read_all(&mut t), b"generic");
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<TestTempFile>();
+        assert_send_sync::<TestTempDir>();
+    }
+
+    #[test]
+    fn test_concurrent_create_write_drop() {
+        let handles: Vec<_> = (0..8).map(|i| {
+            std::thread::spawn(move || {
+                (0..32).map(|_| {
+                    let mut t = TestTempFile::new_in_temp_dir(String::from(FILE_NAME)).unwrap();
+                    let content = format!("thread {}", i);
+                    t.write_all(content.as_bytes()).unwrap();
+                    assert_eq!(t.read_to_string().unwrap(), content);
+                    let path = t.path().to_path_buf();
+                    drop(t);
+                    assert!(!path.exists());
+                    path
+                }).collect::<Vec<_>>()
+            })
+        }).collect();
+        let paths: Vec<PathBuf> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        let unique: std::collections::HashSet<&PathBuf> = paths.iter().collect();
+        assert_eq!(unique.len(), paths.len());
+    }
+
+    #[test]
+    fn test_len() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        assert_eq!(t.len().unwrap(), 0);
+        assert!(t.is_empty().unwrap());
+        t.write_all(b"some bytes").unwrap();
+        t.seek(SeekFrom::Start(3)).unwrap();
+        assert_eq!(t.len().unwrap(), 10);
+        assert!(!t.is_empty().unwrap());
+        assert_eq!(t.stream_position().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"some bytes").unwrap();
+        t.rewind().unwrap();
+        assert_eq!(t.stream_position().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"first payload").unwrap();
+        t.truncate().unwrap();
+        assert_eq!(t.len().unwrap(), 0);
+        t.write_all(b"second").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "second");
+    }
+
+    #[test]
+    fn test_sync() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"some bytes").unwrap();
+        t.sync().unwrap();
+        assert_eq!(std::fs::read(t.path()).unwrap(), b"some bytes");
+    }
+
+    #[test]
+    fn test_new_seeded() {
+        let name = String::from("seeded_file.txt");
+        let t = TestTempFile::new_seeded(name.clone(), 1234).unwrap();
+        let first = t.path().to_path_buf();
+        drop(t);
+        let t = TestTempFile::new_seeded(name.clone(), 1234).unwrap();
+        assert_eq!(t.path(), first);
+        let e = TestTempFile::new_seeded(name, 1234).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_try_clone_seeded() {
+        let t = TestTempFile::new_seeded(String::from("seeded_clone.txt"), 99).unwrap();
+        let clone = t.try_clone().unwrap();
+        assert_ne!(t.path(), clone.path());
+    }
+
+    #[test]
+    fn test_set_permissions_readonly() {
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        let path = t.path().to_path_buf();
+        let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        t.set_permissions(permissions).unwrap();
+        assert!(std::fs::metadata(&path).unwrap().permissions().readonly());
+        drop(t);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"some bytes").unwrap();
+        let debug = format!("{:?}", t);
+        assert!(debug.contains(&format!("{:?}", t.path())), "{}", debug);
+        assert!(debug.contains("len: 10"), "{}", debug);
+        assert!(!debug.contains("random_number"), "{}", debug);
+    }
+
+    #[test]
+    fn test_display() {
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        assert_eq!(t.to_string(), t.path().display().to_string());
+    }
+
+    #[test]
+    fn test_content_equals() {
+        let content: Vec<u8> = (0..20_000).map(|i| (i % 251) as u8).collect();
+        let mut a = TestTempFile::new_with_content(String::from(FILE_NAME), &content).unwrap();
+        let mut b = TestTempFile::new_with_content(String::from(FILE_NAME), &content).unwrap();
+        a.seek(SeekFrom::Start(3)).unwrap();
+        b.seek(SeekFrom::Start(7)).unwrap();
+        assert!(a.content_equals(&mut b).unwrap());
+        assert_eq!(a.stream_position().unwrap(), 3);
+        assert_eq!(b.stream_position().unwrap(), 7);
+
+        b.seek(SeekFrom::Start(15_000)).unwrap();
+        b.write_all(b"x").unwrap();
+        assert!(!a.content_equals(&mut b).unwrap());
+
+        let mut shorter = TestTempFile::new_with_content(String::from(FILE_NAME), &content[..10]).unwrap();
+        assert!(!a.content_equals(&mut shorter).unwrap());
+
+        let mut buffered = TestTempFile::builder().buffered(64 * 1024).name(String::from(FILE_NAME)).build().unwrap();
+        buffered.write_all(&content).unwrap();
+        let mut plain = TestTempFile::new_with_content(String::from(FILE_NAME), &content).unwrap();
+        assert_eq!(buffered.len().unwrap(), 0);
+        assert!(buffered.content_equals(&mut plain).unwrap());
+        assert!(plain.content_equals(&mut buffered).unwrap());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mmap_readonly() {
+        let mut t = TestTempFile::builder().buffered(1024).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_all(b"some mapped content").unwrap();
+        let map = t.mmap_readonly().unwrap();
+        assert_eq!(&map[..], b"some mapped content");
+        drop(map);
+    }
+
+    #[cfg(feature = "hashing")]
+    #[test]
+    fn test_content_hash_matches() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"hello world").unwrap();
+        assert!(t.content_hash_matches("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9").unwrap());
+        assert!(t.content_hash_matches("B94D27B9934D3E08A52E52D7DA7DABFAC484EFE37A5380EE9088F7ACE2EFCDE9").unwrap());
+        assert!(!t.content_hash_matches("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855").unwrap());
+    }
+
+    #[cfg(feature = "hashing")]
+    #[test]
+    fn test_sha256_hex() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"hello world").unwrap();
+        t.seek(SeekFrom::Start(4)).unwrap();
+        assert_eq!(
+            t.sha256_hex().unwrap(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        assert_eq!(t.stream_position().unwrap(), 4);
+
+        let mut empty = TestTempFile::new(String::from(FILE_NAME));
+        assert_eq!(
+            empty.sha256_hex().unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_write_str() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_str("some text").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "some text");
+    }
+
+    #[test]
+    fn test_append_line() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.append_line("one").unwrap();
+        t.rewind().unwrap();
+        t.append_line("two").unwrap();
+        t.append_line("three").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "one\ntwo\nthree\n");
+    }
+
+    #[test]
+    fn test_reopen_readonly() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"some bytes").unwrap();
+        t.flush().unwrap();
+        let mut reader = t.reopen_readonly().unwrap();
+        let mut content = String::new();
+        reader.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "some bytes");
+        assert_eq!(t.stream_position().unwrap(), 10);
+        assert!(reader.write_all(b"more").is_err());
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"some bytes").unwrap();
+        let metadata = t.metadata().unwrap();
+        assert_eq!(metadata.len(), 10);
+        assert!(metadata.is_file());
+        assert!(metadata.modified().is_ok());
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let content: Vec<u8> = (0..5000).map(|i| (i % 256) as u8).collect();
+        let mut source = std::io::Cursor::new(content.clone());
+        let mut t = TestTempFile::from_reader(String::from(FILE_NAME), &mut source).unwrap();
+        assert_eq!(t.stream_position().unwrap(), 0);
+        let mut read = Vec::new();
+        t.read_to_end(&mut read).unwrap();
+        assert_eq!(read, content);
+    }
+
+    #[test]
+    fn test_preallocate() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.preallocate(4096).unwrap();
+        assert_eq!(t.len().unwrap(), 4096);
+        assert_eq!(t.stream_position().unwrap(), 0);
+        let mut buffer = vec![1; 4096];
+        t.read_exact(&mut buffer).unwrap();
+        assert!(buffer.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_null_byte_in_name() {
+        let e = TestTempFile::try_new(String::from("bad\0name.txt")).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        let e = TestTempFile::try_new(String::from("bad\0dir/name.txt")).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_reserved_character_in_name() {
+        let e = TestTempFile::try_new(String::from("what?.txt")).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_with_deletes_on_panic() {
+        let path = std::sync::Mutex::new(PathBuf::new());
+        let result = std::panic::catch_unwind(|| {
+            TestTempFile::with(String::from(FILE_NAME), |t| {
+                *path.lock().unwrap() = t.path().to_path_buf();
+                panic!("inside the closure");
+            })
+        });
+        assert!(result.is_err());
+        let path = path.into_inner().unwrap_or_else(|e| e.into_inner());
+        assert!(path.to_string_lossy().starts_with("test_file_"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_cleanup_orphans() {
+        let dir = TestTempDir::new(String::from("orphans"));
+        let orphan = dir.child(&format!("data_{}.txt", name::tagged(12345678901234)));
+        let bare = dir.child(&format!("_{}", name::tagged(98765432109876)));
+        let unrelated = dir.child("data.txt");
+        let short = dir.child("log_1.txt");
+        let timestamp = dir.child("screenshot_1700000000.png");
+        let backup = dir.child("backup_20240101123456.tar");
+        for path in &[&orphan, &bare, &unrelated, &short, &timestamp, &backup] {
+            std::fs::write(path, b"left over").unwrap();
+        }
+        assert_eq!(cleanup_orphans(dir.path(), std::time::Duration::from_secs(3600)).unwrap(), 0);
+        assert_eq!(cleanup_orphans(dir.path(), std::time::Duration::from_secs(0)).unwrap(), 2);
+        assert!(!orphan.exists());
+        assert!(!bare.exists());
+        assert!(unrelated.exists());
+        assert!(short.exists());
+        assert!(timestamp.exists());
+        assert!(backup.exists());
+    }
+
+    #[test]
+    fn test_copy_to() {
+        let dir = TestTempDir::new(String::from("copy_to"));
+        let dest = dir.child("snapshot.txt");
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"first").unwrap();
+        assert_eq!(t.copy_to(&dest).unwrap(), 5);
+        t.write_all(b" second").unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"first");
+        assert_eq!(t.read_to_string().unwrap(), "first second");
+        let path = t.path().to_path_buf();
+        drop(t);
+        assert!(!path.exists());
+        assert!(dest.exists());
+    }
+
+    #[test]
+    fn test_try_from_str() {
+        use std::convert::TryInto;
+
+        let mut t: TestTempFile = FILE_NAME.try_into().unwrap();
+        assert_eq!(t.filename, FILE_NAME);
+        t.write_all(b"converted").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "converted");
+
+        let result: Result<TestTempFile> = "bad\0name.txt".try_into();
+        assert!(matches!(result.unwrap_err(), TempFileError::InvalidName(_)));
+        assert_eq!(TestTempFile::named(FILE_NAME).filename, FILE_NAME);
+    }
+
+    #[test]
+    fn test_line_count() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        assert_eq!(t.line_count().unwrap(), 0);
+        t.write_all(b"a\nb\nc").unwrap();
+        assert_eq!(t.line_count().unwrap(), 3);
+        t.write_all(b"\n").unwrap();
+        assert_eq!(t.line_count().unwrap(), 3);
+        assert_eq!(t.stream_position().unwrap(), 6);
+        assert_eq!(t.byte_count().unwrap(), 6);
+    }
+
+    #[test]
+    fn test_new_with_extension() {
+        let t = TestTempFile::new_with_extension("report", "csv").unwrap();
+        assert_eq!(t.path().extension().and_then(|e| e.to_str()), Some("csv"));
+        assert_eq!(t.path(), Path::new(&format!("report_{}.csv", name::tagged(t.random_number))));
+
+        let t = TestTempFile::new_with_extension("archive.tar", "gz").unwrap();
+        assert_eq!(t.path(), Path::new(&format!("archive.tar_{}.gz", name::tagged(t.random_number))));
+    }
+
+    #[test]
+    fn test_new_with_empty_extension() {
+        let t = TestTempFile::new_with_extension("report", "").unwrap();
+        assert_eq!(t.path(), Path::new(&format!("report_{}", name::tagged(t.random_number))));
+        assert_eq!(t.path().extension(), None);
+    }
+
+    #[test]
+    fn test_dual_cursor() {
+        let mut t = TestTempFile::builder().dual_cursor(true).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_all(b"first ").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "first ");
+        t.write_all(b"second").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "first second");
+
+        let mut t = TestTempFile::builder().dual_cursor(true).build().unwrap();
+        let mut buf = [0; 3];
+        t.write_all(b"abc").unwrap();
+        t.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"abc");
+        t.write_all(b"def").unwrap();
+        t.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"def");
+        assert_eq!(t.stream_position().unwrap(), 6);
+    }
+
+    #[test]
+    fn test_dual_cursor_rewinding_helpers() {
+        let mut t = TestTempFile::builder().dual_cursor(true).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_all(b"a\nb\n").unwrap();
+        for _ in 0..2 {
+            let lines: Vec<String> = t.lines().unwrap().map(|l| l.unwrap()).collect();
+            assert_eq!(lines, vec!["a", "b"]);
+            let lines: Vec<String> = (&mut t).into_iter().map(|l| l.unwrap()).collect();
+            assert_eq!(lines, vec!["a", "b"]);
+            let chunks: Vec<Vec<u8>> = t.chunks(3).unwrap().map(|c| c.unwrap()).collect();
+            assert_eq!(chunks, vec![b"a\nb".to_vec(), b"\n".to_vec()]);
+        }
+        assert_eq!(t.stream_position().unwrap(), 4);
+
+        let mut downstream = Vec::new();
+        assert_eq!(t.drain_to(&mut downstream).unwrap(), 4);
+        t.write_all(b"c\n").unwrap();
+        assert_eq!(t.drain_to(&mut downstream).unwrap(), 2);
+        assert_eq!(downstream, b"a\nb\nc\n");
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"captured").unwrap();
+        let mut snapshot = t.snapshot().unwrap();
+        assert_eq!(t.stream_position().unwrap(), 8);
+        t.write_all(b" later").unwrap();
+
+        let mut first = String::new();
+        snapshot.read_to_string(&mut first).unwrap();
+        snapshot.seek(SeekFrom::Start(0)).unwrap();
+        let mut second = String::new();
+        snapshot.read_to_string(&mut second).unwrap();
+        assert_eq!(first, "captured");
+        assert_eq!(second, "captured");
+    }
+
+    #[test]
+    fn test_sync_all_and_sync_data() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"durable").unwrap();
+        t.sync_all().unwrap();
+        assert_eq!(std::fs::read(t.path()).unwrap(), b"durable");
+        t.write_all(b" data").unwrap();
+        t.sync_data().unwrap();
+        let mut content = String::new();
+        std::fs::File::open(t.path()).unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "durable data");
+        t.close().unwrap();
+        t.sync_all().unwrap();
+        t.sync_data().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_symlink() {
+        let dir = TestTempDir::new(String::from("symlinks"));
+        let link = dir.child("link.txt");
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"through the link").unwrap();
+        t.create_symlink(&link).unwrap();
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read(&link).unwrap(), b"through the link");
+        assert_eq!(t.create_symlink(&link).unwrap_err().kind(), ErrorKind::AlreadyExists);
+
+        let path = t.path().to_path_buf();
+        drop(t);
+        assert!(!path.exists());
+        assert!(std::fs::symlink_metadata(&link).is_err());
+    }
+
+    #[test]
+    fn test_try_clone_handle() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        let mut handle = t.try_clone_handle().unwrap();
+        handle.write_all(b"from the handle").unwrap();
+        assert_eq!(t.stream_position().unwrap(), 15);
+        handle.seek(SeekFrom::Start(5)).unwrap();
+        let mut content = String::new();
+        Read::read_to_string(&mut t, &mut content).unwrap();
+        assert_eq!(content, "the handle");
+
+        let path = t.path().to_path_buf();
+        drop(t);
+        assert!(!path.exists());
+        drop(handle);
+    }
+
+    #[test]
+    fn test_tmp_suffix_and_commit() {
+        let dir = TestTempDir::new(String::from("commit"));
+        let builder = TestTempFile::builder().dir(dir.path().to_path_buf()).tmp_suffix(true);
+        let mut t = builder.clone().name(String::from("output.json")).build().unwrap();
+        assert!(t.path().to_string_lossy().ends_with(".json.tmp"));
+        t.write_all(b"{}").unwrap();
+        let tmp_path = t.path().to_path_buf();
+        let path = t.commit("output.json").unwrap();
+        assert_eq!(path, dir.child("output.json"));
+        assert!(!tmp_path.exists());
+        assert_eq!(std::fs::read(&path).unwrap(), b"{}");
+        let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+
+        let t = builder.build().unwrap();
+        let tmp_path = t.path().to_path_buf();
+        drop(t);
+        assert!(!tmp_path.exists());
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"0123456789").unwrap();
+        t.seek(SeekFrom::End(0)).unwrap();
+        let lengths: Vec<usize> = t.chunks(4).unwrap().map(|c| c.unwrap().len()).collect();
+        assert_eq!(lengths, vec![4, 4, 2]);
+        let chunks: Vec<Vec<u8>> = t.chunks(5).unwrap().map(|c| c.unwrap()).collect();
+        assert_eq!(chunks, vec![b"01234".to_vec(), b"56789".to_vec()]);
+        assert_eq!(t.chunks(0).err().unwrap().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_new_in_fresh_dir() {
+        let t = TestTempFile::new_in_fresh_dir(String::from(FILE_NAME)).unwrap();
+        let path = t.path().to_path_buf();
+        let dir = path.parent().unwrap().to_path_buf();
+        assert!(dir.starts_with(temp_dir()));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        drop(t);
+        assert!(!path.exists());
+        assert!(!dir.exists());
+
+        let t = TestTempFile::new_in_fresh_dir(String::from(FILE_NAME)).unwrap();
+        let dir = t.path().parent().unwrap().to_path_buf();
+        std::fs::write(dir.join("extra.txt"), b"left behind").unwrap();
+        drop(t);
+        assert!(!dir.exists());
+
+        let t = TestTempFile::new_in_fresh_dir(String::from(FILE_NAME)).unwrap();
+        let path = t.keep();
+        assert!(path.exists());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_position() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        assert_eq!(t.position().unwrap(), 0);
+        t.write_all(b"12345").unwrap();
+        assert_eq!(t.position().unwrap(), 5);
+        assert_eq!(t.position().unwrap(), 5);
+        t.rewind().unwrap();
+        assert_eq!(t.position().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_buffered() {
+        let mut t = TestTempFile::builder().buffered(64 * 1024).name(String::from(FILE_NAME)).build().unwrap();
+        let mut expected = String::new();
+        for i in 0..10_000 {
+            let record = format!("record {}\n", i);
+            t.write_all(record.as_bytes()).unwrap();
+            expected.push_str(&record);
+        }
+        assert!(t.len().unwrap() < expected.len() as u64);
+        t.flush().unwrap();
+        assert_eq!(t.len().unwrap(), expected.len() as u64);
+        assert_eq!(std::fs::read_to_string(t.path()).unwrap(), expected);
+
+        t.write_all(b"unflushed").unwrap();
+        expected.push_str("unflushed");
+        assert_eq!(t.read_to_string().unwrap(), expected);
+        assert_eq!(t.line_count().unwrap(), 10_001);
+    }
+
+    #[test]
+    fn test_flush_before_read() {
+        let mut t = TestTempFile::builder().buffered(1024).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_all(b"buffered").unwrap();
+        Seek::rewind(&mut t).unwrap();
+        let mut content = String::new();
+        Read::read_to_string(&mut t, &mut content).unwrap();
+        assert_eq!(content, "buffered");
+
+        let mut t = TestTempFile::builder()
+            .buffered(1024)
+            .dual_cursor(true)
+            .name(String::from(FILE_NAME))
+            .build()
+            .unwrap();
+        t.write_all(b"buffered").unwrap();
+        let mut content = String::new();
+        Read::read_to_string(&mut t, &mut content).unwrap();
+        assert_eq!(content, "buffered");
+
+        let mut t = TestTempFile::builder()
+            .buffered(1024)
+            .dual_cursor(true)
+            .flush_before_read(false)
+            .name(String::from(FILE_NAME))
+            .build()
+            .unwrap();
+        t.write_all(b"buffered").unwrap();
+        let mut content = String::new();
+        Read::read_to_string(&mut t, &mut content).unwrap();
+        assert_eq!(content, "");
+        t.flush().unwrap();
+        Read::read_to_string(&mut t, &mut content).unwrap();
+        assert_eq!(content, "buffered");
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut t = TestTempFile::new(String::from("nested_delete/test_file.txt"));
+        let path = t.path().to_path_buf();
+        assert!(TestTempFile::exists(&path));
+        t.delete();
+        assert!(!TestTempFile::exists(&path));
+        assert!(!TestTempFile::exists("nested_delete"));
+        t.delete();
+        drop(t);
+        assert!(!TestTempFile::exists(&path));
+    }
+
+    #[test]
+    fn test_set_mtime() {
+        use std::time::{Duration, SystemTime};
+
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"old").unwrap();
+        let past = SystemTime::now() - Duration::from_secs(3 * 24 * 60 * 60);
+        t.set_mtime(past).unwrap();
+        let modified = t.metadata().unwrap().modified().unwrap();
+        let difference = modified.duration_since(past).unwrap_or_else(|e| e.duration());
+        assert!(difference < Duration::from_secs(2), "{:?}", difference);
+    }
+
+    #[test]
+    fn test_sibling_entries() {
+        let dir = TestTempDir::new(String::from("siblings"));
+        let t = TestTempFile::builder().dir(dir.path().to_path_buf()).name(String::from("foo.dat")).build().unwrap();
+        assert_eq!(t.parent_dir(), dir.path());
+        assert!(t.sibling_entries().unwrap().is_empty());
+        std::fs::write(dir.child("foo.idx"), b"index").unwrap();
+        std::fs::create_dir(dir.child("nested")).unwrap();
+        assert_eq!(t.sibling_entries().unwrap(), vec![dir.child("foo.idx")]);
+
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        assert_eq!(t.parent_dir(), Path::new(""));
+        assert!(!t.sibling_entries().unwrap().contains(&t.path().to_path_buf()));
+    }
+
+    #[test]
+    fn test_write_at() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_at(100, b"tail").unwrap();
+        assert_eq!(t.len().unwrap(), 104);
+        assert_eq!(t.position().unwrap(), 0);
+        t.write_all(b"head").unwrap();
+        let content = t.read_to_vec().unwrap();
+        assert_eq!(&content[..4], b"head");
+        assert!(content[4..100].iter().all(|&b| b == 0));
+        assert_eq!(&content[100..], b"tail");
+    }
+
+    #[test]
+    fn test_read_at() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"0123456789abcdefghij").unwrap();
+        t.seek(SeekFrom::Start(3)).unwrap();
+        let mut buf = [0; 4];
+        assert_eq!(t.read_at(8, &mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"89ab");
+        assert_eq!(t.position().unwrap(), 3);
+        assert_eq!(t.read_at(18, &mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"ij");
+        assert_eq!(t.read_at(20, &mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_close() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"before close").unwrap();
+        t.close().unwrap();
+        let e = t.write_all(b"after close").unwrap_err();
+        assert!(e.to_string().contains("was closed"), "{}", e);
+        assert!(t.read_to_string().is_err());
+        assert_eq!(t.len().unwrap(), 12);
+        t.close().unwrap();
+        assert_eq!(std::fs::read(t.path()).unwrap(), b"before close");
+        let path = t.path().to_path_buf();
+        drop(t);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_with_options() {
+        let mut options = std::fs::OpenOptions::new();
+        options.read(true).truncate(true);
+        let mut t = TestTempFile::with_options(String::from(FILE_NAME), options).unwrap();
+        assert!(t.path().exists());
+        t.write_all(b"custom").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "custom");
+        let path = t.path().to_path_buf();
+        drop(t);
+        assert!(!path.exists());
+
+        let mut options = std::fs::OpenOptions::new();
+        options.append(true);
+        let mut t = TestTempFile::with_options(String::from(FILE_NAME), options).unwrap();
+        t.write_all(b"a").unwrap();
+        t.rewind().unwrap();
+        t.write_all(b"b").unwrap();
+        assert!(t.read_to_string().is_err());
+        assert_eq!(std::fs::read(t.path()).unwrap(), b"ab");
+    }
+
+    #[test]
+    fn test_into_iterator() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"first\nsecond\n").unwrap();
+        t.seek(SeekFrom::End(0)).unwrap();
+        let mut lines = Vec::new();
+        for line in &mut t {
+            lines.push(line.unwrap());
+        }
+        assert_eq!(lines, vec!["first", "second"]);
+
+        t.close().unwrap();
+        let results: Vec<std::io::Result<String>> = (&mut t).into_iter().collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+
+    #[test]
+    fn test_regenerate() {
+        let mut t = TestTempFile::new(String::from("regenerate/test_file.txt"));
+        t.write_all(b"first run").unwrap();
+        let old = t.path().to_path_buf();
+        t.regenerate().unwrap();
+        assert!(!old.exists());
+        assert_ne!(t.path(), old);
+        assert_eq!(t.path().parent(), old.parent());
+        assert!(t.path().exists());
+        assert!(t.is_empty().unwrap());
+        assert_eq!(t.path(), Path::new(&format!("regenerate/test_file_{}.txt", name::tagged(t.random_number))));
+
+        t.write_all(b"second run").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "second run");
+        let path = t.path().to_path_buf();
+        drop(t);
+        assert!(!path.exists());
+        assert!(!Path::new("regenerate").exists());
+    }
+
+    #[test]
+    fn test_new_in_manifest_subdir() {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let subdir = format!("target/manifest_subdir_{}", name::process_unique());
+        let t = TestTempFile::new_in_manifest_subdir(&subdir, String::from(FILE_NAME)).unwrap();
+        let path = t.path().to_path_buf();
+        assert!(path.starts_with(manifest_dir.join(&subdir)));
+        assert!(path.exists());
+        drop(t);
+        assert!(!path.exists());
+        assert!(!manifest_dir.join(&subdir).exists());
+    }
+
+    #[test]
+    fn test_random_id() {
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        let name = t.path().file_name().unwrap().to_str().unwrap().to_string();
+        assert_eq!(name, format!("test_file_{}.txt", name::tagged(t.random_id())));
+        let other = TestTempFile::new(String::from(FILE_NAME));
+        assert_ne!(t.random_id(), other.random_id());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_new_anonymous() {
+        let dir = TestTempDir::new(String::from("anonymous"));
+        let filename = dir.child(FILE_NAME).to_string_lossy().into_owned();
+        let mut t = TestTempFile::new_anonymous(filename).unwrap();
+        t.write_all(b"only through the handle").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "only through the handle");
+        assert!(!t.path().exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+        assert!(t.reopen_readonly().is_err());
+        drop(t);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_new_os() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let t = TestTempFile::new_os(OsStr::from_bytes(b"new_os_dir/caf\xe9.txt")).unwrap();
+        let path = t.path().to_path_buf();
+        let name = path.file_name().unwrap().as_bytes();
+        assert!(name.starts_with(b"caf\xe9_"));
+        assert!(name.ends_with(b".txt"));
+        assert_eq!(path.parent(), Some(Path::new("new_os_dir")));
+        assert!(path.exists());
+        drop(t);
+        assert!(!path.exists());
+        assert!(!Path::new("new_os_dir").exists());
+
+        let t = TestTempFile::new_os(OsStr::new(FILE_NAME)).unwrap();
+        assert!(t.path().to_str().unwrap().starts_with("test_file_"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_contents_matches() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"starting\nerror: 42\n").unwrap();
+        assert!(t.contents_matches(r"error: \d+").unwrap());
+        assert!(t.contents_matches(r"(?m)^starting$").unwrap());
+        assert!(!t.contents_matches(r"warning: \d+").unwrap());
+        let e = t.contents_matches(r"error: (\d+").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_commit_on_drop() {
+        let dir = TestTempDir::new(String::from("commit_on_drop"));
+        let target = dir.child("target.txt");
+        std::fs::write(&target, b"old contents").unwrap();
+
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"new contents").unwrap();
+        let path = t.path().to_path_buf();
+        t.commit_on_drop(target.clone());
+        assert_eq!(std::fs::read(&target).unwrap(), b"old contents");
+        drop(t);
+        assert!(!path.exists());
+        assert_eq!(std::fs::read(&target).unwrap(), b"new contents");
+
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        let path = t.path().to_path_buf();
+        drop(t);
+        assert!(!path.exists());
+        assert_eq!(std::fs::read(&target).unwrap(), b"new contents");
+    }
+
+    #[test]
+    fn test_read_header() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"HDR1 body").unwrap();
+        let mut header = [0; 4];
+        t.read_header(&mut header).unwrap();
+        assert_eq!(&header, b"HDR1");
+        assert_eq!(t.position().unwrap(), 4);
+
+        let mut header = [0; 16];
+        let e = t.read_header(&mut header).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_truncate_to() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"0123456789").unwrap();
+        t.truncate_to(4).unwrap();
+        assert_eq!(t.len().unwrap(), 4);
+        assert_eq!(t.position().unwrap(), 4);
+        assert_eq!(t.read_to_vec().unwrap(), b"0123");
+
+        t.seek(SeekFrom::Start(2)).unwrap();
+        t.truncate_to(8).unwrap();
+        assert_eq!(t.position().unwrap(), 2);
+        assert_eq!(t.len().unwrap(), 8);
+        let mut tail = [0xff; 4];
+        t.read_at(4, &mut tail).unwrap();
+        assert_eq!(tail, [0; 4]);
+    }
+
+    #[test]
+    fn test_adopt() {
+        let dir = TestTempDir::new(String::from("adopt"));
+        let path = dir.child("tool_output.txt");
+        std::fs::write(&path, b"written elsewhere").unwrap();
+        let mut t = TestTempFile::adopt(path.clone()).unwrap();
+        assert_eq!(t.path(), path);
+        assert_eq!(t.read_to_string().unwrap(), "written elsewhere");
+        t.write_all(b", then appended").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "written elsewhere, then appended");
+        drop(t);
+        assert!(!path.exists());
+
+        let e = TestTempFile::adopt(path).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.fill(0xAB, 5000).unwrap();
+        assert_eq!(t.len().unwrap(), 5000);
+        assert_eq!(t.position().unwrap(), 0);
+        let mut sample = [0; 8];
+        t.read_at(2500, &mut sample).unwrap();
+        assert_eq!(sample, [0xAB; 8]);
+        assert!(t.read_to_vec().unwrap().iter().all(|&b| b == 0xAB));
+    }
+
+    #[test]
+    fn test_timed_write() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        let data = [0x5A; 1024];
+        let elapsed = t.timed_write(&data, 100).unwrap();
+        assert!(elapsed > std::time::Duration::from_secs(0));
+        assert_eq!(t.len().unwrap(), (data.len() * 100) as u64);
+    }
+
+    #[test]
+    fn test_for_test() {
+        let t = TestTempFile::for_test("tests::for_test/nested", String::from(FILE_NAME)).unwrap();
+        let name = t.path().to_str().unwrap();
+        assert!(name.starts_with("tests__for_test_nested__test_file_"), "{}", name);
+        assert!(name.ends_with(".txt"));
+        assert_eq!(t.path().parent(), Some(Path::new("")));
+
+        let t = TestTempFile::for_test("for_test", String::from("for_test_dir/data.json")).unwrap();
+        let path = t.path().to_path_buf();
+        assert!(path.file_name().unwrap().to_str().unwrap().starts_with("for_test__data_"));
+        assert_eq!(path.parent(), Some(Path::new("for_test_dir")));
+        drop(t);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_as_file() {
+        let mut t = TestTempFile::builder().buffered(64).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_all(b"buffered ").unwrap();
+        assert_eq!(t.as_file().metadata().unwrap().len(), 0);
+        t.as_file_mut().write_all(b"direct").unwrap();
+        assert_eq!(t.as_file().metadata().unwrap().len(), 15);
+        assert_eq!(t.read_to_string().unwrap(), "buffered direct");
+    }
+
+    #[cfg(all(feature = "locking", any(unix, windows)))]
+    #[test]
+    fn test_lock_exclusive() {
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        t.lock_exclusive().unwrap();
+        let path = t.path().to_path_buf();
+        let contender = move || lock::lock_exclusive(&std::fs::File::open(&path).unwrap(), false).unwrap();
+        assert!(!std::thread::spawn(contender.clone()).join().unwrap());
+        t.unlock().unwrap();
+        assert!(std::thread::spawn(contender).join().unwrap());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_new_with_rng() {
+        use rand::{Rng, SeedableRng};
+        use rand::rngs::StdRng;
+
+        let expected = StdRng::seed_from_u64(7).random::<u64>();
+        let t = TestTempFile::new_with_rng(String::from(FILE_NAME), &mut StdRng::seed_from_u64(7)).unwrap();
+        assert_eq!(t.path(), Path::new(&format!("test_file_{}.txt", name::tagged(expected))));
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let u = TestTempFile::new_with_rng(String::from(FILE_NAME), &mut rng).unwrap();
+        assert_ne!(u.path(), t.path());
+        assert_eq!(u.random_id(), {
+            let mut replay = StdRng::seed_from_u64(7);
+            replay.random::<u64>();
+            replay.random::<u64>()
+        });
+    }
+
+    #[test]
+    fn test_tee() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        let mut mirror = Vec::new();
+        {
+            let mut tee = t.tee(&mut mirror);
+            tee.write_all(b"first message\n").unwrap();
+            write!(tee, "second {}", 2).unwrap();
+            tee.flush().unwrap();
+        }
+        assert_eq!(mirror, b"first message\nsecond 2");
+        assert_eq!(t.read_to_vec().unwrap(), mirror);
+    }
+
+    #[test]
+    fn test_text_and_binary_writer() {
+        let mut t = TestTempFile::builder().buffered(64).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_all(b"\x00\x01").unwrap();
+        t.binary_writer().write_all(&[0xfe, 0xff]).unwrap();
+        {
+            let mut text = t.text_writer();
+            write!(text, "line one\nline").unwrap();
+            write!(text, " two").unwrap();
+        }
+        t.binary_writer().write_all(&[0x02]).unwrap();
+        assert_eq!(t.read_to_vec().unwrap(), b"\x00\x01\xfe\xffline one\nline two\x02");
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"0123456789").unwrap();
+        t.seek(SeekFrom::Start(4)).unwrap();
+        t.compact().unwrap();
+        assert_eq!(t.len().unwrap(), 4);
+        assert_eq!(t.position().unwrap(), 4);
+        assert_eq!(t.read_to_string().unwrap(), "0123");
+    }
+
+    #[test]
+    fn test_set_thread_default_dir() {
+        let dir = TestTempDir::new(String::from("thread_default"));
+        TestTempFile::set_thread_default_dir(Some(dir.path().to_path_buf()));
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        assert_eq!(t.path().parent(), Some(dir.path()));
+        let nested = TestTempFile::new(String::from("nested/test_file.txt"));
+        assert_eq!(nested.path().parent(), Some(dir.child("nested").as_path()));
+        let other_thread = std::thread::spawn(|| TestTempFile::new(String::from(FILE_NAME))).join().unwrap();
+        assert_eq!(other_thread.path().parent(), Some(Path::new("")));
+
+        TestTempFile::set_thread_default_dir(None);
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        assert_eq!(t.path().parent(), Some(Path::new("")));
+    }
+
+    #[test]
+    fn test_read_range() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"abcdefghijklmnopqrst").unwrap();
+        t.seek(SeekFrom::Start(3)).unwrap();
+        assert_eq!(t.read_range(5, 10).unwrap(), b"fghij");
+        assert_eq!(t.read_range(20, 20).unwrap(), b"");
+        assert_eq!(t.position().unwrap(), 3);
+        assert_eq!(t.read_range(15, 25).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(t.read_range(0, u64::MAX).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(t.read_range(u64::MAX - 1, u64::MAX).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(t.read_range(10, 5).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(t.position().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_new_empty_filename() {
+        let t = TestTempFile::new(String::new());
+        assert_eq!(t.path(), Path::new(&format!("tmp_{}", name::tagged(t.random_id()))));
+        assert_eq!(t.filename, "");
+        let t = TestTempFile::new(String::from("empty_name_dir/"));
+        assert_eq!(t.path(), Path::new(&format!("empty_name_dir/tmp_{}", name::tagged(t.random_id()))));
+    }
+
+    #[test]
+    fn test_concat() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"abc").unwrap();
+        t.seek(SeekFrom::Start(1)).unwrap();
+        let mut other = TestTempFile::new(String::from(FILE_NAME));
+        other.write_all(b"def").unwrap();
+        other.seek(SeekFrom::Start(2)).unwrap();
+        assert_eq!(t.concat(&mut other).unwrap(), 3);
+        assert_eq!(other.position().unwrap(), 2);
+        assert_eq!(t.position().unwrap(), 6);
+        assert_eq!(t.read_to_string().unwrap(), "abcdef");
+    }
+
+    #[test]
+    fn test_dump() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"valid \xff invalid").unwrap();
+        t.seek(SeekFrom::Start(3)).unwrap();
+        t.dump("dump test").unwrap();
+        assert_eq!(t.position().unwrap(), 3);
+        t.write_all(b"-").unwrap();
+        assert_eq!(t.read_to_vec().unwrap(), b"val-d \xff invalid");
+    }
+
+    #[test]
+    fn test_assert_fully_read() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"0123456789").unwrap();
+        let mut half = [0; 5];
+        t.read_exact(&mut half).unwrap();
+        let e = t.assert_fully_read().unwrap_err();
+        assert!(e.to_string().contains("5 of 10 bytes were never read"), "{}", e);
+        t.read_exact(&mut half).unwrap();
+        t.assert_fully_read().unwrap();
+
+        let mut t = TestTempFile::builder().dual_cursor(true).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_all(b"written").unwrap();
+        assert!(t.assert_fully_read().is_err());
+        Read::read_to_end(&mut t, &mut Vec::new()).unwrap();
+        t.assert_fully_read().unwrap();
+    }
+
+    #[test]
+    fn test_replace_contents() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"the initial, longer payload").unwrap();
+        t.replace_contents(b"new payload").unwrap();
+        assert_eq!(t.position().unwrap(), 0);
+        assert_eq!(t.len().unwrap(), 11);
+        assert_eq!(t.read_to_string().unwrap(), "new payload");
+    }
+
+    #[test]
+    fn test_sibling_with_extension() {
+        let data = TestTempFile::new(String::from("sibling.dat"));
+        let index = data.sibling_with_extension("idx").unwrap();
+        assert_eq!(index.path().file_stem(), data.path().file_stem());
+        assert_eq!(index.path().extension().unwrap(), "idx");
+        assert_eq!(data.path().extension().unwrap(), "dat");
+        assert_eq!(index.random_id(), data.random_id());
+        assert_eq!(data.sibling_with_extension("idx").unwrap_err().kind(), ErrorKind::AlreadyExists);
+        assert_eq!(data.sibling_with_extension("a/b").unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let index_path = index.path().to_path_buf();
+        drop(index);
+        assert!(!index_path.exists());
+        assert!(data.path().exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_chown() {
+        use std::os::unix::fs::MetadataExt;
+
+        let t = TestTempFile::new(String::from(FILE_NAME));
+        let metadata = t.metadata().unwrap();
+        t.chown(Some(metadata.uid()), Some(metadata.gid())).unwrap();
+        if metadata.uid() != 0 {
+            eprintln!("skipping the rest of test_chown, it needs root");
+            return;
+        }
+        t.chown(None, Some(1)).unwrap();
+        assert_eq!(t.metadata().unwrap().gid(), 1);
+        assert_eq!(t.metadata().unwrap().uid(), 0);
+    }
+
+    #[test]
+    fn test_flushed_len() {
+        let mut t = TestTempFile::builder().buffered(4096).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_all(&[1; 100]).unwrap();
+        assert!(t.len().unwrap() < 100);
+        assert_eq!(t.flushed_len().unwrap(), 100);
+        assert_eq!(t.len().unwrap(), 100);
+        assert_eq!(t.position().unwrap(), 100);
+    }
+
+    #[test]
+    fn test_retain_if() {
+        let contains_panic = |content: &[u8]| content.windows(5).any(|w| w == b"PANIC");
+        let t = TestTempFile::new_with_content(String::from(FILE_NAME), b"thread PANIC at").unwrap();
+        let path = t.retain_if(contains_panic).unwrap().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"thread PANIC at");
+        std::fs::remove_file(path).unwrap();
+
+        let t = TestTempFile::new_with_content(String::from(FILE_NAME), b"all good").unwrap();
+        let path = t.path().to_path_buf();
+        assert_eq!(t.retain_if(contains_panic).unwrap(), None);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_stream_len() {
+        let mut t = TestTempFile::builder().buffered(64).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_all(b"0123456789").unwrap();
+        t.seek(SeekFrom::Start(3)).unwrap();
+        assert_eq!(t.stream_position().unwrap(), 3);
+        assert_eq!(t.stream_len().unwrap(), 10);
+        assert_eq!(t.stream_position().unwrap(), 3);
+        t.seek(SeekFrom::End(0)).unwrap();
+        t.write_all(b"ab").unwrap();
+        assert_eq!(t.stream_len().unwrap(), 12);
+        assert_eq!(t.stream_position().unwrap(), 12);
+    }
+
+    #[test]
+    fn test_check_size_limit() {
+        let mut t = TestTempFile::builder().warn_if_larger_than(100).name(String::from(FILE_NAME)).build().unwrap();
+        t.write_all(&[0; 100]).unwrap();
+        t.check_size_limit().unwrap();
+        t.preallocate(101).unwrap();
+        let e = t.check_size_limit().unwrap_err();
+        assert!(e.to_string().contains("101 bytes, larger than the limit of 100 bytes"), "{}", e);
+
+        let mut unlimited = TestTempFile::new(String::from(FILE_NAME));
+        unlimited.preallocate(1 << 20).unwrap();
+        unlimited.check_size_limit().unwrap();
+    }
+
+    #[test]
+    fn test_append_from_path() {
+        let fixture = std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")).unwrap();
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"prefix\n").unwrap();
+        let appended = t.append_from_path(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")).unwrap();
+        assert_eq!(appended, fixture.len() as u64);
+        assert_eq!(t.position().unwrap(), 7 + appended);
+        let mut expected = b"prefix\n".to_vec();
+        expected.extend_from_slice(&fixture);
+        assert_eq!(t.read_to_vec().unwrap(), expected);
+
+        let e = t.append_from_path("no_such_fixture.txt").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+    }
+
+    /// Writer that fails with `Interrupted` once, then accepts at most 3 bytes per call
+    struct InterruptedOnce {
+        interrupted: bool,
+        written: Vec<u8>
+    }
+
+    impl Write for InterruptedOnce {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if !self.interrupted {
+                self.interrupted = true;
+                return Err(std::io::Error::from(ErrorKind::Interrupted));
+            }
+            let n = buf.len().min(3);
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_all_retrying() {
+        let mut writer = InterruptedOnce { interrupted: false, written: Vec::new() };
+        write_all_retrying(&mut writer, b"some bytes").unwrap();
+        assert!(writer.interrupted);
+        assert_eq!(writer.written, b"some bytes");
+
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all_retrying(b"some bytes").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "some bytes");
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        t.write_all(b"before ").unwrap();
+        let path = t.path().to_path_buf();
+        let mut file = t.into_inner();
+        file.write_all(b"after").unwrap();
+        drop(file);
+        assert_eq!(std::fs::read(&path).unwrap(), b"before after");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mode_bits() {
+        use std::os::unix::fs::OpenOptionsExt;
+        use std::os::unix::fs::PermissionsExt;
+
+        // a file asking for everything shows which bits the umask takes away
+        let d = TestTempDir::new(String::from("mode_bits"));
+        let probe = std::fs::OpenOptions::new().write(true).create_new(true).mode(0o777).open(d.path().join("probe")).unwrap();
+        let allowed = probe.metadata().unwrap().permissions().mode() & 0o777;
+
+        let t = TestTempFileBuilder::new().mode(0o644).build().unwrap();
+        assert_eq!(t.mode_bits().unwrap(), 0o644 & allowed);
+    }
+
+    #[test]
+    fn test_read_to_string_lossy() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"valid \xc3\x28 bytes").unwrap();
+        assert_eq!(t.read_to_string().unwrap_err().kind(), ErrorKind::InvalidData);
+        t.seek(SeekFrom::Start(6)).unwrap();
+        let content = t.read_to_string_lossy().unwrap();
+        assert!(content.contains('\u{FFFD}'));
+        assert_eq!(content, "valid \u{FFFD}( bytes");
+        assert_eq!(t.position().unwrap(), 6);
+    }
+
+    #[test]
+    fn test_named_temp() {
+        let mut t = TestTempFile::named_temp().unwrap();
+        let other = TestTempFile::named_temp().unwrap();
+        assert_ne!(t.path(), other.path());
+        assert_eq!(t.path().parent().unwrap(), temp_dir());
+        t.write_all(b"migrated").unwrap();
+        assert_eq!(t.read_to_string().unwrap(), "migrated");
+        let path = t.path().to_path_buf();
+        drop(t);
+        assert!(!path.exists());
+
+        let dir = TestTempDir::new(String::from("named_temp_persist"));
+        let mut t = TestTempFile::named_temp().unwrap();
+        t.write_all(b"kept").unwrap();
+        let persisted = t.persist(dir.path().join("kept.txt")).unwrap();
+        assert_eq!(std::fs::read(persisted).unwrap(), b"kept");
+    }
+
+    #[test]
+    fn test_as_bytes_cache() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"first").unwrap();
+        assert_eq!(t.as_bytes().unwrap(), b"first");
+        // changed behind its back, the cached copy is still served
+        std::fs::write(t.path(), b"other").unwrap();
+        assert_eq!(t.as_bytes().unwrap(), b"first");
+
+        t.seek(SeekFrom::End(0)).unwrap();
+        t.write_all(b" more").unwrap();
+        assert_eq!(t.as_bytes().unwrap(), b"other more");
+        assert_eq!(t.position().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_from_path_copy() {
+        let dir = TestTempDir::new(String::from("from_path_copy"));
+        let fixture = dir.path().join("golden.txt");
+        std::fs::write(&fixture, b"golden input").unwrap();
+
+        let mut t = TestTempFile::from_path_copy(String::from(FILE_NAME), &fixture).unwrap();
+        assert_eq!(t.position().unwrap(), 0);
+        assert_eq!(t.read_to_string().unwrap(), "golden input");
+        t.truncate().unwrap();
+        t.write_all(b"mutated").unwrap();
+        let path = t.path().to_path_buf();
+        drop(t);
+        assert!(!path.exists());
+        assert_eq!(std::fs::read(&fixture).unwrap(), b"golden input");
+
+        let e = TestTempFile::from_path_copy(String::from(FILE_NAME), dir.path().join("missing.txt")).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_verify_roundtrip() {
+        let payload: Vec<u8> = (0..=255).cycle().take(3 * CHUNK_SIZE).collect();
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), &[7; 4 * CHUNK_SIZE]).unwrap();
+        assert!(t.verify_roundtrip(&payload).unwrap());
+        assert_eq!(t.len().unwrap(), payload.len() as u64);
+        assert!(t.verify_roundtrip(b"").unwrap());
+
+        let corrupted = t.roundtrip_with(&payload, |t, data| {
+            t.write_all(&data[..10])?;
+            t.write_all(b"X")?;
+            t.write_all(&data[11..])
+        }).unwrap();
+        assert!(!corrupted);
+    }
+
+    #[test]
+    fn test_on_mount() {
+        let first = TestTempDir::new(String::from("first_mount"));
+        let second = TestTempDir::new(String::from("second_mount"));
+        let mut source = TestTempFile::on_mount(first.path(), String::from(FILE_NAME)).unwrap();
+        let other = TestTempFile::on_mount(second.path(), String::from(FILE_NAME)).unwrap();
+        assert_eq!(source.path().parent().unwrap(), first.path());
+        assert_eq!(other.path().parent().unwrap(), second.path());
+
+        source.write_all(b"crossing over").unwrap();
+        let dest = source.persist(second.path().join("persisted.txt")).unwrap();
+        assert_eq!(std::fs::read(dest).unwrap(), b"crossing over");
+
+        let missing = first.path().join("not_mounted");
+        let e = TestTempFile::on_mount(&missing, String::from(FILE_NAME)).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+        assert!(e.to_string().contains("not_mounted"), "{}", e);
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn test_drain_to() {
+        let mut t = TestTempFile::new(String::from(FILE_NAME));
+        let mut downstream = Vec::new();
+        t.write_all(b"first batch\n").unwrap();
+        assert_eq!(t.drain_to(&mut downstream).unwrap(), 12);
+        assert!(t.is_empty().unwrap());
+        assert_eq!(t.position().unwrap(), 0);
+
+        t.write_all(b"second\n").unwrap();
+        assert_eq!(t.drain_to(&mut downstream).unwrap(), 7);
+        assert_eq!(downstream, b"first batch\nsecond\n");
+        assert_eq!(t.drain_to(&mut downstream).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_append_normalized() {
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), b"unix\n").unwrap();
+        t.append_normalized("dos\r\nmac\rmixed\r\r\n").unwrap();
+        t.append_normalized("end").unwrap();
+        let content = t.read_to_string().unwrap();
+        assert_eq!(content, "unix\ndos\nmac\nmixed\n\nend");
+        assert!(!content.contains('\r'));
+        assert_eq!(t.line_count().unwrap(), 6);
+    }
+
+    #[test]
+    fn test_touch() {
+        let t = TestTempFile::new_with_content(String::from(FILE_NAME), b"unchanged").unwrap();
+        let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(60 * 60);
+        t.set_mtime(an_hour_ago).unwrap();
+        let before = t.metadata().unwrap().modified().unwrap();
+        t.touch().unwrap();
+        assert!(t.metadata().unwrap().modified().unwrap() > before);
+        assert_eq!(t.len().unwrap(), 9);
+        assert_eq!(std::fs::read(t.path()).unwrap(), b"unchanged");
+    }
+
+    #[test]
+    fn test_equals_reader() {
+        let content: Vec<u8> = (0..=255).cycle().take(2 * CHUNK_SIZE + 5).collect();
+        let mut t = TestTempFile::new_with_content(String::from(FILE_NAME), &content).unwrap();
+        t.seek(SeekFrom::Start(3)).unwrap();
+        assert!(t.equals_reader(&mut std::io::Cursor::new(&content)).unwrap());
+        assert_eq!(t.position().unwrap(), 3);
+
+        let mut differing = content.clone();
+        differing[CHUNK_SIZE + 1] ^= 1;
+        assert!(!t.equals_reader(&mut std::io::Cursor::new(&differing)).unwrap());
+        assert!(!t.equals_reader(&mut std::io::Cursor::new(&content[..content.len() - 1])).unwrap());
+        let mut longer = content.clone();
+        longer.push(0);
+        assert!(!t.equals_reader(&mut std::io::Cursor::new(&longer)).unwrap());
+    }
+
+    #[test]
+    fn test_new_in_temp_dir() {
+        let t = TestTempFile::new_in_temp_dir(String::from(FILE_NAME)).unwrap();
+        let path = t.final_filename.clone();
+        assert!(path.is_absolute());
+        assert!(path.starts_with(std::env::temp_dir()));
+        assert!(path.exists());
+        drop(t);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,92 @@
+use std::fs::File;
+
+/// Takes an exclusive advisory lock on `file`, `Ok(false)` when `wait` is `false` and
+/// another handle holds it. Uses `flock` on unix and `LockFileEx` on Windows.
+pub(crate) fn lock_exclusive(file: &File, wait: bool) -> std::io::Result<bool> {
+    imp::lock_exclusive(file, wait)
+}
+
+/// Releases a lock taken with [`lock_exclusive`]
+pub(crate) fn unlock(file: &File) -> std::io::Result<()> {
+    imp::unlock(file)
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::fs::File;
+    use std::os::unix::io::AsRawFd;
+
+    pub(super) fn lock_exclusive(file: &File, wait: bool) -> std::io::Result<bool> {
+        let operation = if wait { libc::LOCK_EX } else { libc::LOCK_EX | libc::LOCK_NB };
+        match flock(file, operation) {
+            Err(ref e) if e.raw_os_error() == Some(libc::EWOULDBLOCK) => Ok(false),
+            result => result.map(|()| true)
+        }
+    }
+
+    pub(super) fn unlock(file: &File) -> std::io::Result<()> {
+        flock(file, libc::LOCK_UN)
+    }
+
+    fn flock(file: &File, operation: libc::c_int) -> std::io::Result<()> {
+        loop {
+            // the descriptor stays open for the call
+            if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+                return Ok(());
+            }
+            let e = std::io::Error::last_os_error();
+            if e.kind() != std::io::ErrorKind::Interrupted {
+                return Err(e);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::fs::File;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::ERROR_LOCK_VIOLATION;
+    use windows_sys::Win32::Storage::FileSystem::{LockFileEx, UnlockFile, LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY};
+    use windows_sys::Win32::System::IO::OVERLAPPED;
+
+    pub(super) fn lock_exclusive(file: &File, wait: bool) -> std::io::Result<bool> {
+        let flags = if wait { LOCKFILE_EXCLUSIVE_LOCK } else { LOCKFILE_EXCLUSIVE_LOCK | LOCKFILE_FAIL_IMMEDIATELY };
+        // locks the whole file from offset 0, the offset being the zeroed fields of OVERLAPPED
+        let locked = unsafe {
+            let mut overlapped: OVERLAPPED = std::mem::zeroed();
+            LockFileEx(file.as_raw_handle() as _, flags, 0, u32::MAX, u32::MAX, &mut overlapped)
+        };
+        if locked != 0 {
+            return Ok(true);
+        }
+        let e = std::io::Error::last_os_error();
+        if e.raw_os_error() == Some(ERROR_LOCK_VIOLATION as i32) {
+            Ok(false)
+        } else {
+            Err(e)
+        }
+    }
+
+    pub(super) fn unlock(file: &File) -> std::io::Result<()> {
+        // the handle stays open for the call
+        if unsafe { UnlockFile(file.as_raw_handle() as _, 0, 0, u32::MAX, u32::MAX) } != 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use std::fs::File;
+
+    pub(super) fn lock_exclusive(_file: &File, _wait: bool) -> std::io::Result<bool> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "file locking isn't supported on this platform"))
+    }
+
+    pub(super) fn unlock(_file: &File) -> std::io::Result<()> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "file locking isn't supported on this platform"))
+    }
+}
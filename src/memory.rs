@@ -0,0 +1,145 @@
+use std::io::{BufRead, Cursor, Read, Seek, SeekFrom, Write};
+
+/// An in-memory stand-in for [`TestTempFile`](crate::TestTempFile) for tests that only need
+/// something implementing `Read + Write + Seek`. Nothing ever touches the disk.
+///
+/// The helper methods mirror the ones on `TestTempFile` so code written against either
+/// one reads the same.
+///
+/// # Examples
+/// ```
+/// use std::io::Write;
+/// use test_temp_file::InMemoryTempFile;
+/// let mut t = InMemoryTempFile::new();
+/// t.write_all(b"some bytes").unwrap();
+/// assert_eq!(t.read_to_string().unwrap(), "some bytes");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryTempFile {
+    cursor: Cursor<Vec<u8>>
+}
+
+impl InMemoryTempFile {
+    pub fn new() -> InMemoryTempFile {
+        InMemoryTempFile::default()
+    }
+
+    /// Creates a file that already holds `content`, with the cursor at the start
+    pub fn new_with_content(content: &[u8]) -> InMemoryTempFile {
+        InMemoryTempFile { cursor: Cursor::new(content.to_vec()) }
+    }
+
+    /// Length of the contents in bytes, the cursor isn't moved
+    pub fn len(&self) -> crate::Result<u64> {
+        Ok(self.cursor.get_ref().len() as u64)
+    }
+
+    /// `true` when nothing was written yet, the cursor isn't moved
+    pub fn is_empty(&self) -> crate::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Moves the cursor back to the start, same as [`Seek::rewind`]
+    pub fn rewind(&mut self) -> crate::Result<()> {
+        self.cursor.set_position(0);
+        Ok(())
+    }
+
+    /// Empties the contents and rewinds
+    pub fn truncate(&mut self) -> crate::Result<()> {
+        self.cursor.get_mut().clear();
+        self.rewind()
+    }
+
+    /// Rewinds and reads everything into a String, leaving the cursor at the end
+    pub fn read_to_string(&mut self) -> crate::Result<String> {
+        let mut content = String::new();
+        self.rewind()?;
+        self.cursor.read_to_string(&mut content)?;
+        Ok(content)
+    }
+
+    /// Rewinds and reads everything into a Vec, leaving the cursor at the end
+    pub fn read_to_vec(&mut self) -> crate::Result<Vec<u8>> {
+        let mut content = Vec::new();
+        self.rewind()?;
+        self.cursor.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    /// Writes `s` at the current cursor position
+    pub fn write_str(&mut self, s: &str) -> crate::Result<()> {
+        self.write_all(s.as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes `s` followed by a newline at the end
+    pub fn append_line(&mut self, s: &str) -> crate::Result<()> {
+        self.seek(SeekFrom::End(0))?;
+        self.write_str(s)?;
+        self.write_str("\n")
+    }
+
+    /// Iterates over the lines starting from the beginning, regardless of the cursor position
+    pub fn lines(&mut self) -> crate::Result<impl Iterator<Item = std::io::Result<String>> + '_> {
+        self.rewind()?;
+        Ok((&mut self.cursor).lines())
+    }
+
+    /// The contents written so far
+    pub fn as_bytes(&self) -> &[u8] {
+        self.cursor.get_ref()
+    }
+}
+
+impl Write for InMemoryTempFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.cursor.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Read for InMemoryTempFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.cursor.read(buf)
+    }
+}
+
+impl Seek for InMemoryTempFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> { self.cursor.seek(pos) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestTempFile;
+
+    fn roundtrip<T: Read + Write + Seek>(t: &mut T) -> String {
+        t.write_all(b"first\nsecond\n").unwrap();
+        t.seek(SeekFrom::Start(0)).unwrap();
+        let mut content = String::new();
+        t.read_to_string(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn test_write_rewind_read() {
+        let mut t = InMemoryTempFile::new();
+        assert_eq!(roundtrip(&mut t), "first\nsecond\n");
+        t.append_line("third").unwrap();
+        let lines: Vec<String> = t.lines().unwrap().map(|l| l.unwrap()).collect();
+        assert_eq!(lines, vec!["first", "second", "third"]);
+        assert_eq!(t.len().unwrap(), 19);
+        t.truncate().unwrap();
+        assert!(t.is_empty().unwrap());
+    }
+
+    #[test]
+    fn test_same_code_runs_on_disk() {
+        let mut t = TestTempFile::new(String::from("in_memory_compare.txt"));
+        assert_eq!(roundtrip(&mut t), roundtrip(&mut InMemoryTempFile::new()));
+    }
+}
@@ -0,0 +1,276 @@
+use crate::TempFileError;
+use std::borrow::Cow;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How many random names are tried before giving up on finding one that doesn't exist yet
+pub(crate) const MAX_ATTEMPTS: usize = 16;
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Random number for the default naming scheme, all 64 bits from [`rand::rng`] mixed with
+/// a process-wide counter, so even a generator repeating itself doesn't repeat a name
+#[cfg(feature = "rand")]
+pub(crate) fn process_unique() -> u64 {
+    use rand::Rng;
+
+    let random: u64 = rand::rng().random();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    random ^ splitmix64(count)
+}
+
+/// Without the `rand` feature the names come from [`counter_unique`]
+#[cfg(not(feature = "rand"))]
+pub(crate) fn process_unique() -> u64 {
+    counter_unique()
+}
+
+/// [`process_unique`] without `rand`, the counter offset by a start value taken from the
+/// time and process id on first use and spread over all 64 bits. SplitMix64 is a
+/// bijection, so no two calls within one process return the same number.
+#[cfg_attr(feature = "rand", allow(dead_code))]
+fn counter_unique() -> u64 {
+    static START: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+    let start = *START.get_or_init(|| {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        nanos ^ (u64::from(std::process::id()) << 32)
+    });
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    splitmix64(start.wrapping_add(count))
+}
+
+/// Generator for seeded names, a [`StdRng`](rand::rngs::StdRng) seeded with `seed`
+#[cfg(feature = "rand")]
+pub(crate) fn seeded(seed: u64) -> impl FnMut() -> u64 {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    move || rng.random()
+}
+
+/// Generator for seeded names, without the `rand` feature a SplitMix64 sequence
+#[cfg(not(feature = "rand"))]
+pub(crate) fn seeded(seed: u64) -> impl FnMut() -> u64 {
+    let mut state = seed;
+    move || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        splitmix64(state)
+    }
+}
+
+/// The SplitMix64 output function, spreads consecutive inputs over all 64 bits
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Writes `number` out in the base given by the length of `alphabet`, most significant
+/// character first. With `len` the result is cut down to its last `len` characters or
+/// padded at the front with characters drawn from a generator seeded with `number`.
+pub(crate) fn encode(number: u64, alphabet: &[u8], len: Option<usize>) -> String {
+    let base = alphabet.len() as u64;
+    let mut encoded = Vec::new();
+    let mut rest = number;
+    loop {
+        encoded.push(alphabet[(rest % base) as usize]);
+        rest /= base;
+        if rest == 0 {
+            break;
+        }
+    }
+    if let Some(len) = len {
+        encoded.truncate(len);
+        let mut draw = seeded(number);
+        while encoded.len() < len {
+            encoded.push(alphabet[(draw() % base) as usize]);
+        }
+    }
+    encoded.reverse();
+    encoded.into_iter().map(char::from).collect()
+}
+
+/// Longest generated file name in bytes, below the 255 most filesystems allow
+pub(crate) const MAX_NAME_LEN: usize = 200;
+
+/// `prefix` cut down so that together with `rest` more bytes it still fits in
+/// [`MAX_NAME_LEN`], keeping a trailing `_` separator. Fails with
+/// [`ErrorKind::InvalidInput`] when not even the separator fits.
+pub(crate) fn shorten_prefix(prefix: &str, rest: usize) -> std::io::Result<Cow<'_, str>> {
+    if prefix.len() + rest <= MAX_NAME_LEN {
+        return Ok(Cow::Borrowed(prefix));
+    }
+    let separator = if prefix.ends_with('_') { "_" } else { "" };
+    let budget = match MAX_NAME_LEN.checked_sub(rest + separator.len()) {
+        Some(budget) => budget,
+        None => return Err(invalid_name(prefix, "leaves no room for the random component and suffix"))
+    };
+    let mut end = budget.min(prefix.len() - separator.len());
+    while !prefix.is_char_boundary(end) {
+        end -= 1;
+    }
+    Ok(Cow::Owned(format!("{}{}", &prefix[..end], separator)))
+}
+
+/// Marker in front of the default random component, so [`is_generated`] can tell the
+/// crate's files apart from files that merely end in a number, like timestamps
+const TAG: &str = "ttf";
+
+/// Digits in the default random component, enough for any `u64`
+const TAGGED_DIGITS: usize = 20;
+
+/// The default random component, [`TAG`] followed by `number` as [`TAGGED_DIGITS`]
+/// zero-padded decimal digits
+pub(crate) fn tagged(number: u64) -> String {
+    format!("{}{:0width$}", TAG, number, width = TAGGED_DIGITS)
+}
+
+/// `true` when `name` was generated by this crate with the default random component,
+/// meaning it contains `_` and a [`tagged`] component, then either the end of the name
+/// or an extension
+pub(crate) fn is_generated(name: &str) -> bool {
+    let marker = format!("_{}", TAG);
+    name.match_indices(marker.as_str()).any(|(i, _)| {
+        let rest = &name[i + marker.len()..];
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        digits == TAGGED_DIGITS && (digits == rest.len() || rest[digits..].starts_with('.'))
+    })
+}
+
+/// Characters Windows doesn't allow in file names, besides the path separators
+#[cfg(windows)]
+const RESERVED: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Fails with [`ErrorKind::InvalidInput`] when `name` can't be part of a file name,
+/// it may not contain null bytes, and on Windows any of `<>:"|?*`
+pub(crate) fn validate(name: &str) -> std::io::Result<()> {
+    if name.contains('\0') {
+        return Err(invalid_name(name, "contains a null byte"));
+    }
+    #[cfg(windows)]
+    {
+        if name.contains(RESERVED) {
+            return Err(invalid_name(name, "contains a character reserved on Windows"));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn invalid_name(name: &str, reason: &str) -> std::io::Error {
+    TempFileError::InvalidName(format!("invalid file name {:?}: {}", name, reason)).into()
+}
+
+/// A `{path}.lock` file held while a candidate name is being claimed, removed on drop
+pub(crate) struct NameLock(PathBuf);
+
+impl NameLock {
+    /// Creates the lock next to `path`, failing with [`ErrorKind::AlreadyExists`] while
+    /// someone else holds it
+    pub(crate) fn acquire(path: &Path) -> std::io::Result<NameLock> {
+        let mut lock = path.as_os_str().to_os_string();
+        lock.push(".lock");
+        let lock = PathBuf::from(lock);
+        std::fs::OpenOptions::new().write(true).create_new(true).open(&lock)?;
+        Ok(NameLock(lock))
+    }
+}
+
+impl Drop for NameLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Calls `create` with a number from `draw` until it succeeds or fails with anything
+/// other than [`ErrorKind::AlreadyExists`], at most `attempts` times,
+/// returning the number that worked.
+pub(crate) fn create_unique<T, D, F>(attempts: usize, mut draw: D, mut create: F) -> std::io::Result<(u64, T)>
+    where D: FnMut() -> u64, F: FnMut(u64) -> std::io::Result<T>
+{
+    for _ in 0..attempts {
+        let random_number = draw();
+        match create(random_number) {
+            Ok(created) => return Ok((random_number, created)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e)
+        }
+    }
+    Err(TempFileError::CollisionExhausted { attempts }.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("fixture.txt").is_ok());
+        let e = validate("fix\0ture.txt").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert_eq!(validate("a:b").is_err(), cfg!(windows));
+    }
+
+    #[test]
+    fn test_is_generated() {
+        let random = tagged(process_unique());
+        assert_eq!(tagged(42), "ttf00000000000000000042");
+        assert!(is_generated(&format!("data_{}.json", random)));
+        assert!(is_generated(&format!("data_{}", random)));
+        assert!(is_generated(&format!("_{}", random)));
+        assert!(is_generated(&format!("data_{}.json.tmp", random)));
+        assert!(!is_generated("data.json"));
+        assert!(!is_generated("log_1.txt"));
+        assert!(!is_generated(&format!("data_{}x.json", random)));
+        assert!(!is_generated(&format!("data_{}1.json", random)));
+        assert!(!is_generated("screenshot_1700000000.png"));
+        assert!(!is_generated("backup_20240101123456.tar"));
+        assert!(!is_generated("trace_12345678901234567890.log"));
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(1234, b"0123456789", None), "1234");
+        assert_eq!(encode(0, b"0123456789", None), "0");
+        assert_eq!(encode(255, b"0123456789abcdef", None), "ff");
+        assert_eq!(encode(1234, b"0123456789", Some(2)), "34");
+        let padded = encode(1234, b"0123456789", Some(8));
+        assert!(padded.ends_with("1234"), "{}", padded);
+        assert_eq!(padded.len(), 8);
+        assert_eq!(padded, encode(1234, b"0123456789", Some(8)));
+    }
+
+    #[test]
+    fn test_shorten_prefix() {
+        assert_eq!(shorten_prefix("data_", 10).unwrap(), "data_");
+        let long = format!("{}_", "é".repeat(150));
+        let short = shorten_prefix(&long, 20).unwrap();
+        assert!(short.len() + 20 <= MAX_NAME_LEN);
+        assert!(short.ends_with("é_"));
+        assert_eq!(shorten_prefix("data_", MAX_NAME_LEN).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_counter_unique() {
+        let numbers: std::collections::HashSet<u64> = (0..1000).map(|_| counter_unique()).collect();
+        assert_eq!(numbers.len(), 1000);
+    }
+
+    #[test]
+    fn test_seeded() {
+        let first: Vec<u64> = std::iter::repeat_with(seeded(3)).take(4).collect();
+        let second: Vec<u64> = std::iter::repeat_with(seeded(3)).take(4).collect();
+        assert_eq!(first, second);
+        assert_ne!(first[0], first[1]);
+    }
+
+    #[test]
+    fn test_process_unique() {
+        let numbers: std::collections::HashSet<u64> = (0..1000).map(|_| process_unique()).collect();
+        assert_eq!(numbers.len(), 1000);
+    }
+}
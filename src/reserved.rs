@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+
+/// A unique path in the place a [`TestTempFile`](crate::TestTempFile) would be created,
+/// without the file itself, returned by [`TestTempFile::reserve`](crate::TestTempFile::reserve).
+/// For handing a path to a tool that creates the file on its own. Whatever is at the path
+/// when the guard is dropped is deleted, along with any directories created for it, unless
+/// `TEST_TEMP_FILE_KEEP` is set like for a [`TestTempFile`](crate::TestTempFile).
+///
+/// # Examples
+/// ```
+/// use test_temp_file::TestTempFile;
+/// let reserved = TestTempFile::reserve(String::from("output.txt")).unwrap();
+/// assert!(!reserved.path().exists());
+/// std::fs::write(reserved.path(), b"created by the tool").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ReservedTempPath {
+    path: PathBuf,
+    created_dirs: Vec<PathBuf>
+}
+
+impl ReservedTempPath {
+    pub(crate) fn new(path: PathBuf, created_dirs: Vec<PathBuf>) -> ReservedTempPath {
+        ReservedTempPath { path, created_dirs }
+    }
+
+    /// The reserved path, nothing exists there until someone creates it
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for ReservedTempPath {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Drop for ReservedTempPath {
+    fn drop(&mut self) {
+        if crate::keep_requested() {
+            if self.path.exists() {
+                eprintln!("test-temp-file: keeping {} because {} is set", self.path.display(), crate::KEEP_VAR);
+            }
+            return;
+        }
+        match crate::remove_file(&self.path) {
+            Ok(()) => (),
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => eprintln!("test-temp-file: failed to delete {}: {}", self.path.display(), e)
+        }
+        crate::builder::remove_dirs(&self.created_dirs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TestTempFile;
+
+    #[test]
+    fn test_reserve() {
+        let reserved = TestTempFile::reserve(String::from("reserved.txt")).unwrap();
+        let other = TestTempFile::reserve(String::from("reserved.txt")).unwrap();
+        assert_ne!(reserved.path(), other.path());
+        let path = reserved.path().to_path_buf();
+        assert!(!path.exists());
+
+        std::fs::write(&path, b"created by the tool").unwrap();
+        drop(reserved);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_reserve_nested() {
+        let reserved = TestTempFile::reserve(String::from("reserved_nested/inner/output.txt")).unwrap();
+        let path = reserved.path().to_path_buf();
+        assert!(path.parent().unwrap().is_dir());
+        assert!(!path.exists());
+        std::fs::write(&path, b"created by the tool").unwrap();
+        drop(reserved);
+        assert!(!path.exists());
+        assert!(!std::path::Path::new("reserved_nested").exists());
+    }
+
+    #[test]
+    fn test_reserve_never_created() {
+        let reserved = TestTempFile::reserve(String::from("reserved.txt")).unwrap();
+        let path = reserved.path().to_path_buf();
+        drop(reserved);
+        assert!(!path.exists());
+    }
+}
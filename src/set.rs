@@ -0,0 +1,119 @@
+use crate::TestTempFile;
+
+/// Owns any number of [`TestTempFile`]s so they're all cleaned up together when the set
+/// is dropped. Files are dropped in reverse creation order, so an entry that refers to an
+/// earlier one (a symlink to it, say) is always removed first.
+///
+/// # Examples
+/// ```
+/// use std::io::Write;
+/// use test_temp_file::TempFileSet;
+/// let mut set = TempFileSet::new();
+/// set.create("input.txt").unwrap().write_all(b"some bytes").unwrap();
+/// set.create("output.txt").unwrap();
+/// assert_eq!(set.len(), 2);
+/// ```
+#[derive(Debug, Default)]
+pub struct TempFileSet {
+    files: Vec<TestTempFile>
+}
+
+impl TempFileSet {
+    pub fn new() -> TempFileSet {
+        TempFileSet::default()
+    }
+
+    /// Creates a file named like [`TestTempFile::new`] does and adds it to the set
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The file name
+    pub fn create(&mut self, name: &str) -> crate::Result<&mut TestTempFile> {
+        let file = TestTempFile::try_new(String::from(name))?;
+        self.files.push(file);
+        Ok(self.files.last_mut().unwrap())
+    }
+
+    /// Adds a file created elsewhere, from a [`TestTempFileBuilder`](crate::TestTempFileBuilder)
+    /// for instance, to the set
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file to take ownership of
+    pub fn insert(&mut self, file: TestTempFile) -> &mut TestTempFile {
+        self.files.push(file);
+        self.files.last_mut().unwrap()
+    }
+
+    /// Number of files in the set
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// `true` when no file was created through the set yet
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Iterates over the files in the order they were created
+    pub fn iter(&self) -> std::slice::Iter<'_, TestTempFile> {
+        self.files.iter()
+    }
+}
+
+impl Drop for TempFileSet {
+    fn drop(&mut self) {
+        while let Some(file) = self.files.pop() {
+            drop(file);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestTempFileBuilder;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_create() {
+        let mut set = TempFileSet::new();
+        assert!(set.is_empty());
+        set.create("first.txt").unwrap().write_all(b"first").unwrap();
+        set.create("second.txt").unwrap().write_all(b"second").unwrap();
+        assert_eq!(set.len(), 2);
+        let names: Vec<&str> = set.iter().map(|t| t.filename.as_str()).collect();
+        assert_eq!(names, vec!["first.txt", "second.txt"]);
+    }
+
+    #[test]
+    fn test_drop_deletes_all() {
+        let mut set = TempFileSet::new();
+        for i in 0..5 {
+            set.create(&format!("file_{}.txt", i)).unwrap();
+        }
+        let paths: Vec<PathBuf> = set.iter().map(|t| t.path().to_path_buf()).collect();
+        assert!(paths.iter().all(|p| p.exists()));
+        drop(set);
+        assert!(paths.iter().all(|p| !p.exists()));
+    }
+
+    #[test]
+    fn test_drop_order_is_lifo() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut set = TempFileSet::new();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            let order = order.clone();
+            let file = TestTempFileBuilder::new()
+                .name(String::from(name))
+                .on_drop(move |_| order.lock().unwrap().push(name))
+                .build()
+                .unwrap();
+            set.insert(file);
+        }
+        drop(set);
+        assert_eq!(*order.lock().unwrap(), vec!["c.txt", "b.txt", "a.txt"]);
+    }
+}
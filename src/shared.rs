@@ -0,0 +1,120 @@
+use crate::TestTempFile;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// A [`TestTempFile`] behind a [`Mutex`], so it can be read and written through a shared
+/// reference. `Read`, `Write` and `Seek` are implemented for `&SharedTestTempFile`,
+/// like they are for `&File`, for APIs that don't take `&mut`.
+///
+/// # Examples
+/// ```
+/// use std::io::Write;
+/// use test_temp_file::{SharedTestTempFile, TestTempFile};
+/// let shared = SharedTestTempFile::new(TestTempFile::new(String::from("file_name.txt")));
+/// (&shared).write_all(b"some bytes").unwrap();
+/// assert_eq!(shared.lock().read_to_string().unwrap(), "some bytes");
+/// ```
+#[derive(Debug)]
+pub struct SharedTestTempFile {
+    path: PathBuf,
+    inner: Mutex<TestTempFile>
+}
+
+impl SharedTestTempFile {
+    pub fn new(file: TestTempFile) -> SharedTestTempFile {
+        SharedTestTempFile { path: file.path().to_path_buf(), inner: Mutex::new(file) }
+    }
+
+    /// Path of the file, available without locking
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Locks the file for calling any of the [`TestTempFile`] helpers. A panic while the
+    /// lock was held doesn't poison it, the file stays usable.
+    pub fn lock(&self) -> MutexGuard<'_, TestTempFile> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Gives back the [`TestTempFile`], which is deleted when dropped as usual
+    pub fn into_inner(self) -> TestTempFile {
+        self.inner.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl From<TestTempFile> for SharedTestTempFile {
+    fn from(file: TestTempFile) -> SharedTestTempFile {
+        SharedTestTempFile::new(file)
+    }
+}
+
+impl AsRef<Path> for SharedTestTempFile {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Write for &SharedTestTempFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.lock().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.lock().flush()
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.lock().write_all(buf)
+    }
+}
+
+impl Read for &SharedTestTempFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.lock().read(buf)
+    }
+}
+
+impl Seek for &SharedTestTempFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> { self.lock().seek(pos) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_record<W>(writer: &W, record: &str) -> std::io::Result<()>
+        where for<'a> &'a W: Write
+    {
+        let mut writer = writer;
+        writeln!(writer, "{}", record)
+    }
+
+    #[test]
+    fn test_write_through_shared_reference() {
+        let shared = SharedTestTempFile::from(TestTempFile::new(String::from("shared.txt")));
+        write_record(&shared, "first").unwrap();
+        write_record(&shared, "second").unwrap();
+        (&shared).seek(SeekFrom::Start(0)).unwrap();
+        let mut content = String::new();
+        (&shared).read_to_string(&mut content).unwrap();
+        assert_eq!(content, "first\nsecond\n");
+
+        let path = shared.path().to_path_buf();
+        drop(shared);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_threads() {
+        let shared = SharedTestTempFile::new(TestTempFile::new(String::from("shared_threads.txt")));
+        std::thread::scope(|scope| {
+            for i in 0..4 {
+                let shared = &shared;
+                scope.spawn(move || write_record(shared, &i.to_string()).unwrap());
+            }
+        });
+        let mut t = shared.into_inner();
+        assert_eq!(t.line_count().unwrap(), 4);
+    }
+}
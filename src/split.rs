@@ -0,0 +1,105 @@
+use crate::TestTempFile;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The reading half of [`TestTempFile::into_reader_writer`], a read-only handle with its
+/// own cursor, starting at the beginning of the file.
+///
+/// The file is deleted once both halves are dropped.
+#[derive(Debug)]
+pub struct TempReader {
+    file: File,
+    guard: Arc<TestTempFile>
+}
+
+/// The writing half of [`TestTempFile::into_reader_writer`], keeping the cursor and any
+/// [`buffered`](crate::TestTempFileBuilder::buffered) writes of the original file.
+/// Writes become visible to the [`TempReader`] once flushed.
+///
+/// The file is deleted once both halves are dropped.
+#[derive(Debug)]
+pub struct TempWriter {
+    file: BufWriter<File>,
+    guard: Arc<TestTempFile>
+}
+
+pub(crate) fn split(mut t: TestTempFile) -> std::io::Result<(TempReader, TempWriter)> {
+    let reader = t.reopen_readonly()?;
+    let writer = t.file.take().ok_or_else(|| t.closed_error())?;
+    let guard = Arc::new(t);
+    Ok((TempReader { file: reader, guard: guard.clone() }, TempWriter { file: writer, guard }))
+}
+
+impl TempReader {
+    /// Path of the shared file
+    pub fn path(&self) -> &Path {
+        self.guard.path()
+    }
+}
+
+impl TempWriter {
+    /// Path of the shared file
+    pub fn path(&self) -> &Path {
+        self.guard.path()
+    }
+}
+
+impl Read for TempReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Seek for TempReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> { self.file.seek(pos) }
+}
+
+impl Write for TempWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for TempWriter {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> { self.file.seek(pos) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_producer_consumer() {
+        let t = TestTempFile::new(String::from("reader_writer.log"));
+        let (mut reader, mut writer) = t.into_reader_writer().unwrap();
+        let path = writer.path().to_path_buf();
+        assert_eq!(reader.path(), path);
+
+        let consumer = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            while received.len() < 10 {
+                let mut buf = [0; 4];
+                let n = reader.read(&mut buf).unwrap();
+                received.extend_from_slice(&buf[..n]);
+            }
+            (reader, received)
+        });
+        writer.write_all(b"first").unwrap();
+        writer.flush().unwrap();
+        writer.write_all(b"after").unwrap();
+        writer.flush().unwrap();
+        let (reader, received) = consumer.join().unwrap();
+        assert_eq!(received, b"firstafter");
+
+        drop(writer);
+        assert!(path.exists());
+        drop(reader);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,59 @@
+//! Runs in its own process since it changes `TEST_TEMP_FILE_KEEP` for the whole process
+
+use std::io::Write;
+use test_temp_file::{TestTempDir, TestTempFile};
+
+#[test]
+fn test_keep_env_var() {
+    std::env::set_var("TEST_TEMP_FILE_KEEP", "1");
+    let t = TestTempFile::new(String::from("kept_by_env.txt"));
+    let path = t.path().to_path_buf();
+    drop(t);
+    assert!(path.exists());
+    std::fs::remove_file(&path).unwrap();
+
+    let reserved = TestTempFile::reserve(String::from("kept_by_env/reserved.txt")).unwrap();
+    let path = reserved.path().to_path_buf();
+    std::fs::write(&path, b"created by the tool").unwrap();
+    drop(reserved);
+    assert!(path.exists());
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_dir(path.parent().unwrap()).unwrap();
+
+    let dir = TestTempDir::new(String::from("kept_by_env_dir"));
+    let path = dir.path().to_path_buf();
+    std::fs::write(dir.child("output.txt"), b"left for inspection").unwrap();
+    drop(dir);
+    assert!(path.join("output.txt").exists());
+    std::fs::remove_dir_all(&path).unwrap();
+
+    let mut anonymous = TestTempFile::new_anonymous(String::from("anonymous_kept.txt")).unwrap();
+    anonymous.write_all(b"never on disk").unwrap();
+    let path = anonymous.path().to_path_buf();
+    drop(anonymous);
+    assert!(!path.exists());
+
+    std::env::set_var("TEST_TEMP_FILE_KEEP", "0");
+    let t = TestTempFile::new(String::from("deleted_anyway.txt"));
+    let path = t.path().to_path_buf();
+    drop(t);
+    assert!(!path.exists());
+
+    let reserved = TestTempFile::reserve(String::from("deleted_anyway/reserved.txt")).unwrap();
+    let path = reserved.path().to_path_buf();
+    std::fs::write(&path, b"created by the tool").unwrap();
+    drop(reserved);
+    assert!(!path.exists());
+    assert!(!path.parent().unwrap().exists());
+
+    let dir = TestTempDir::new(String::from("deleted_anyway_dir"));
+    let path = dir.path().to_path_buf();
+    drop(dir);
+    assert!(!path.exists());
+
+    std::env::remove_var("TEST_TEMP_FILE_KEEP");
+    let t = TestTempFile::new(String::from("deleted_anyway.txt"));
+    let path = t.path().to_path_buf();
+    drop(t);
+    assert!(!path.exists());
+}
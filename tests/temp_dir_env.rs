@@ -0,0 +1,24 @@
+//! Runs in its own process since it changes `TMPDIR` for the whole process
+
+use test_temp_file::{TestTempDir, TestTempFile};
+
+#[cfg(unix)]
+#[test]
+fn test_tmpdir_is_honored() {
+    let scratch = TestTempDir::new_in_temp_dir(String::from("tmpdir_override")).unwrap();
+    let explicit = TestTempDir::new_in_temp_dir(String::from("explicit_dir")).unwrap();
+    let original = std::env::var_os("TMPDIR");
+    std::env::set_var("TMPDIR", scratch.path());
+
+    let t = TestTempFile::new_in_temp_dir(String::from("env_file.txt")).unwrap();
+    assert_eq!(t.path().parent(), Some(scratch.path()));
+    let d = TestTempDir::new_in_temp_dir(String::from("env_dir")).unwrap();
+    assert_eq!(d.path().parent(), Some(scratch.path()));
+    let b = TestTempFile::builder().dir(explicit.path().to_path_buf()).build().unwrap();
+    assert_eq!(b.path().parent(), Some(explicit.path()));
+
+    match original {
+        Some(value) => std::env::set_var("TMPDIR", value),
+        None => std::env::remove_var("TMPDIR")
+    }
+}